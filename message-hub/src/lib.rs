@@ -1,33 +1,74 @@
 //! ArbiLink MessageHub – cross-chain messaging hub on Arbitrum Stylus
 
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 extern crate alloc;
 
-use alloc::vec::Vec;
+mod math;
+#[cfg(test)]
+mod tests;
+
+use alloc::{string::String, vec::Vec};
+use math::{checked_add, checked_sub, mul_div};
 use stylus_sdk::{
-    alloy_primitives::{Address, Bytes, U256, U8, U32},
-    alloy_sol_types::{sol, SolError},
-    call::transfer::transfer_eth,
+    alloy_primitives::{address, b256, Address, Bytes, FixedBytes, B256, U256, U8, U32},
+    alloy_sol_types::{sol, SolError, SolValue},
+    call::{static_call, transfer::transfer_eth, Call},
+    crypto::keccak,
     prelude::*,
+    storage::{StorageU256, StorageVec},
 };
 
 sol! {
+    /// `category` is not a topic: the three indexed slots a non-anonymous event
+    /// allows are taken, so filter on it from the event data.
     event MessageSent(
         uint256 indexed messageId,
         address indexed sender,
-        uint32  destinationChain,
+        uint32  indexed destinationChain,
         address target,
         bytes   data,
-        uint256 fee
+        uint256 fee,
+        bool    isTest,
+        bytes4  category
     );
+    event EncryptionMetaAttached(uint256 indexed messageId, bytes meta);
     event MessageConfirmed(
         uint256 indexed messageId,
         address indexed relayer,
         uint256 timestamp
     );
+    event MessageClaimed(uint256 indexed messageId, address indexed relayer, uint256 expiry);
+    event TargetUpdated(uint256 indexed messageId, address oldTarget, address newTarget);
+    event FeeBumped(uint256 indexed messageId, uint256 added, uint256 newTotal);
+    event DeliveryFailed(uint256 indexed messageId, address indexed relayer);
+    event OwnershipTransferStarted(address indexed previousOwner, address indexed newOwner);
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+    event ConfirmationRevoked(uint256 indexed messageId, address indexed relayer, uint256 clawedBack);
+    event EscrowReleased(uint256 indexed messageId, uint256 amount);
+    event MessageRefunded(uint256 indexed messageId, address indexed to, uint256 amount);
     event RelayerRegistered(address indexed relayer, uint256 stake);
+    event RelayerStatsUpdated(address indexed relayer, uint256 successful, uint256 stake);
     event RelayerExited(address indexed relayer, uint256 returned);
+    event ChallengePeriodUpdated(uint256 oldPeriod, uint256 newPeriod);
+    event RewardsClaimed(address indexed account, uint256 amount);
+    event Escheated(address indexed account, uint256 amount);
+    event UnaccountedSwept(address indexed to, uint256 amount);
+    event FeesWithdrawn(address indexed to, uint256 amount);
+    event FeesMigrated(address indexed to, uint256 amount);
+    event TreasuryFeeAccrued(uint256 indexed messageId, uint256 amount);
+    event TreasuryWithdrawn(address indexed treasury, uint256 amount);
+    event OperatorProposed(address indexed relayer, address indexed operator);
+    event OperatorSet(address indexed relayer, address indexed operator);
+    event StakeDecreased(address indexed relayer, uint256 amount, uint256 newTotal);
     event ChainAdded(uint32 indexed chainId, address receiver, uint256 baseFee);
+    event ChainFeeUpdated(uint32 indexed chainId, uint256 oldFee, uint256 newFee);
+    event ChainEnabled(uint32 indexed chainId);
+    event ChainDisabled(uint32 indexed chainId);
+    event ChainReceiverRotated(uint32 indexed chainId, address newReceiver, uint256 activationTime);
+    event ChainReceiverFinalized(uint32 indexed chainId, address receiver);
+    event StateRootSubmitted(uint32 indexed chainId, bytes32 root);
 
     error ChainNotSupported(uint32 chainId);
     error InsufficientFee(uint256 required, uint256 provided);
@@ -39,26 +80,91 @@ sol! {
     error TransferFailed();
     error ZeroAddress();
     error AlreadyInitialized();
+    error InvalidTarget(address target);
+    error LengthMismatch();
+    error InvalidBps(uint256 bps);
+    error InvalidProof();
+    error SelfRelayForbidden(uint256 messageId);
+    error InvalidPriority(uint8 priority);
+    error RotationNotReady(uint32 chainId, uint256 activationTime);
+    error InsufficientFees(uint256 available, uint256 requested);
+    error OutOfOrder(uint256 messageId, uint256 previousId);
+    error MultiplierTooHigh(uint256 bps, uint256 max);
+    error InvalidChallengePeriod(uint256 period);
+    error NotRefundable(uint256 messageId);
+    error DeliveryDeadlinePassed(uint256 messageId, uint256 deadline);
+    error InvalidDeadline(uint256 deadline);
+    error RelayerCapReached(uint256 maxRelayers);
+    error InvalidSignature();
+    error SignatureExpired(uint256 deadline);
+    error ArithmeticOverflow();
+    error NothingToSweep();
+    error WrongStatus(uint256 messageId, uint8 status);
+    error InvalidProofMode(uint8 mode);
+    error NotEscheatable(address account);
+    error InvalidClaimDeadline(uint256 period, uint256 min);
+    error EncryptionMetaTooLong(uint256 length, uint256 max);
+    error RevokeWindowClosed(uint256 messageId);
+    error RevokeWindowOpen(uint256 messageId, uint256 until);
+    error CannotRegisterLocalChain(uint32 chainId);
+    error DataTooShort(uint32 min, uint256 actual);
+    error Reentrancy();
+    error MessageClaimedByOther(uint256 messageId, address claimedBy);
+    error AlreadyClaimed(uint256 messageId);
+    error InvalidInitParams(uint256 minStake, uint256 challengePeriod);
+    error RateLimited(address relayer, uint256 windowEnd);
+    error InvalidMinStake();
 }
 
 sol_storage! {
+    /// Small fields are grouped so they pack into shared slots:
+    /// slot 0 `sender | destination_chain | status | priority | is_test | category | exists`,
+    /// slots 1-3 `target`, `relayer`, `refund_address`, slots 4-9 one `uint256` each,
+    /// slot 10 `encryption_meta` (longer payloads spill to its hashed slot),
+    /// slot 11 `confirmed_at`, slot 12 `claimed_by | burned | settled`, slots 13-14 `claim_expiry`, `burn`.
     pub struct StoredMessage {
         address sender;
         uint32  destination_chain;
+        uint8   status;
+        uint8   priority;
+        bool    is_test;
+        bytes4  category;
+        bool    exists;
         address target;
+        address relayer;
+        address refund_address;
         uint256 timestamp;
         uint256 fee_paid;
-        uint8   status;
-        address relayer;
+        uint256 treasury_cut;
+        uint256 reward;
+        uint256 sequence;
+        uint256 deadline;
+        bytes   encryption_meta;
+        uint256 confirmed_at;
+        address claimed_by;
+        bool    burned;
+        bool    settled;
+        uint256 claim_expiry;
+        uint256 burn;
     }
     pub struct StoredChainConfig {
         bool    enabled;
         address receiver_address;
         uint256 base_fee;
+        bool    strict_target;
+        address pending_receiver;
+        uint256 activation_time;
+        bool    ordered;
+        uint8   proof_mode;
+        uint32  min_data_len;
     }
     pub struct StoredRelayerInfo {
         bool    active;
         uint256 stake;
+        bool    listed;
+        uint256 successful;
+        uint256 window_start;
+        uint256 window_count;
     }
     #[entrypoint]
     pub struct MessageHub {
@@ -70,20 +176,136 @@ sol_storage! {
         uint256 min_stake;
         uint256 protocol_fee_balance;
         uint256 challenge_period;
+        uint256[] test_messages;
+        address[] relayer_list;
+        uint256 burn_bps;
+        uint256 total_burned;
+        bool    require_third_party_proof;
+        uint256 total_active_stake;
+        uint256 chain_count;
+        uint256 total_confirmed;
+        address treasury;
+        uint256 treasury_bps;
+        uint256 treasury_balance;
+        uint256 min_fee;
+        mapping(uint8 => uint256) priority_multiplier_bps;
+        uint32[] chain_ids;
+        mapping(address => address) relayer_operator;
+        mapping(address => address) operator_relayer;
+        mapping(address => address) pending_operator;
+        uint256 pending_escrow;
+        mapping(address => mapping(uint32 => uint256)) sender_dest_nonce;
+        mapping(address => mapping(uint32 => mapping(uint256 => uint256))) sequenced_ids;
+        uint256 fee_multiplier_bps;
+        mapping(address => mapping(bytes32 => bool)) roles;
+        mapping(address => uint256) pending_withdrawals;
+        uint256 total_claimable;
+        uint256 max_relayers;
+        uint256 active_relayer_count;
+        mapping(address => uint256) meta_nonces;
+        mapping(address => uint256[]) relayer_messages;
+        mapping(uint32 => uint256) fee_ema;
+        uint256 fee_ema_alpha_bps;
+        mapping(uint32 => bytes32) chain_state_root;
+        mapping(address => uint256) accrued_at;
+        uint256 claim_deadline;
+        uint256 revoke_window;
+        mapping(uint32 => uint256) chain_message_count;
+        mapping(uint32 => uint256) chain_confirmed_count;
+        mapping(uint32 => uint256) chain_failed_count;
+        bool    locked;
+        address pending_owner;
+        address previous_owner;
+        uint256 exclusivity_window;
+        uint256 max_confirmations_per_window;
+        uint256 rate_window;
+        mapping(address => uint256) sender_confirmed;
+        uint256 total_failed;
     }
 }
 
+pub const VERSION: &str = "1.0.0";
+pub const VERSION_MAJOR: u8 = 1;
+pub const VERSION_MINOR: u8 = 0;
+
+pub const ADMIN_ROLE: B256         = b256!("a49807205ce4d355092ef5a8a18f56e8913cf4a201fbe287825b095693c21775");
+pub const CHAIN_MANAGER_ROLE: B256 = b256!("23878d7036183277afdccb9a63f858828e2c3714b28756783a9729affff30955");
+pub const FEE_MANAGER_ROLE: B256   = b256!("6c0757dc3e6b28b2580c03fd9e96c274acf4f99d91fbec9b418fa1d70604ff1c");
+
+const DOMAIN_TYPEHASH: B256 = b256!("8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f");
+const SEND_MESSAGE_TYPEHASH: B256 = b256!("e9c4a5e0dfde9175f91d7c12d2cc7598305363149902ab4fa4c9512f1d5ea7e1");
+const DOMAIN_NAME: &str = "ArbiLink MessageHub";
+
 const STATUS_PENDING: u8   = 0;
 const STATUS_CONFIRMED: u8 = 1;
+const STATUS_FAILED: u8    = 3;
+const PROOF_SIGNATURE: u8 = 0;
+const PROOF_MERKLE: u8    = 1;
+const PRIORITY_STANDARD: u8 = 0;
+const PRIORITY_FAST: u8     = 1;
 const RELAYER_REWARD_BPS: u64 = 8_000;
+const MAX_PAGE: u64 = 100;
+const MAX_ENCRYPTION_META: usize = 256;
+const MAX_LEADERBOARD: u64 = 50;
+const BPS_DENOMINATOR: u64 = 10_000;
+const RECEIVER_ROTATION_GRACE: u64 = 86_400;
+const MAX_FEE_MULTIPLIER_BPS: u64 = 50_000;
+const MIN_CHALLENGE_PERIOD: u64 = 60;
+const MAX_CHALLENGE_PERIOD: u64 = 7 * 86_400;
+const MIN_CLAIM_DEADLINE: u64 = 30 * 86_400;
+/// `(confirmed messages, discount bps)`, highest tier first; the top tier is the cap.
+const SENDER_DISCOUNT_TIERS: [(u64, u64); 2] = [(100, 1_000), (10, 500)];
+const DEFAULT_CHAIN_KEY: u32 = 0;
+const BURN_ADDRESS: Address = address!("000000000000000000000000000000000000dEaD");
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
 
 fn enc<E: SolError>(e: E) -> Vec<u8> { e.abi_encode() }
 
+/// `eth_sign` digest of `keccak256(abi.encode(messageId, destinationChain, target))`.
+fn delivery_digest(id: U256, chain_id: u32, target: Address) -> B256 {
+    let inner = keccak((id, chain_id, target).abi_encode_params());
+    keccak([b"\x19Ethereum Signed Message:\n32".as_slice(), inner.as_slice()].concat())
+}
+
+/// Checks `proof`, a concatenation of 32-byte siblings, links `leaf` to `root`.
+/// Pairs are hashed sorted, so the proof carries no left/right flags.
+fn verify_merkle(root: B256, leaf: B256, proof: &[u8]) -> bool {
+    if proof.len() % 32 != 0 { return false; }
+    let node = proof.chunks(32).map(B256::from_slice).fold(leaf, |acc, sib| {
+        let (a, b) = if acc <= sib { (acc, sib) } else { (sib, acc) };
+        keccak([a.as_slice(), b.as_slice()].concat())
+    });
+    node == root
+}
+
+/// `eth_sign` digest of `keccak256(abi.encode(messageId, destinationChain, target, "FAILED"))`,
+/// signed by the receiver when execution on the destination reverted.
+fn failure_digest(id: U256, chain_id: u32, target: Address) -> B256 {
+    let inner = keccak((id, chain_id, target, String::from("FAILED")).abi_encode_params());
+    keccak([b"\x19Ethereum Signed Message:\n32".as_slice(), inner.as_slice()].concat())
+}
+
+pub fn gwei_to_wei(g: u64) -> U256 { U256::from(g) * U256::from(1_000_000_000u64) }
+
+fn page(ids: &StorageVec<StorageU256>, offset: U256, limit: U256) -> Vec<U256> {
+    let len = U256::from(ids.len());
+    if offset >= len { return Vec::new(); }
+    let end = len.min(offset.saturating_add(limit.min(U256::from(MAX_PAGE))));
+    (offset.to::<usize>()..end.to::<usize>()).filter_map(|i| ids.get(i)).collect()
+}
+
 #[public]
 impl MessageHub {
     pub fn initialize(&mut self, min_stake: U256, challenge_period: U256) -> Result<(), Vec<u8>> {
         if self.owner.get() != Address::ZERO { return Err(enc(AlreadyInitialized {})); }
-        self.owner.set(self.vm().msg_sender());
+        if min_stake == U256::ZERO || challenge_period == U256::ZERO {
+            return Err(enc(InvalidInitParams { minStake: min_stake, challengePeriod: challenge_period }));
+        }
+        let deployer = self.vm().msg_sender();
+        self.owner.set(deployer);
+        for role in [ADMIN_ROLE, CHAIN_MANAGER_ROLE, FEE_MANAGER_ROLE] {
+            self.roles.setter(deployer).setter(role).set(true);
+        }
         self.min_stake.set(min_stake);
         self.challenge_period.set(challenge_period);
         Ok(())
@@ -91,49 +313,298 @@ impl MessageHub {
 
     #[payable]
     pub fn send_message(&mut self, destination_chain: u32, target: Address, data: Bytes) -> Result<U256, Vec<u8>> {
-        let ck = U32::from(destination_chain);
-        if !self.supported_chains.getter(ck).enabled.get() {
-            return Err(enc(ChainNotSupported { chainId: destination_chain }));
-        }
-        let req = self.supported_chains.getter(ck).base_fee.get();
-        let val = self.vm().msg_value();
-        if val < req { return Err(enc(InsufficientFee { required: req, provided: val })); }
-        let id = self.message_nonce.get() + U256::from(1u8);
-        self.message_nonce.set(id);
         let sender = self.vm().msg_sender();
-        let ts = U256::from(self.vm().block_timestamp());
+        self.send(sender, destination_chain, target, data, false, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, FixedBytes::ZERO, Bytes::new())
+    }
+
+    /// `send_message` with every optional field: test flag, priority tier, refund
+    /// address and delivery deadline (zero for the sender and no deadline), category,
+    /// and encryption metadata. Passing the defaults is the same as `send_message`.
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_message_extended(
+        &mut self,
+        destination_chain: u32,
+        target: Address,
+        data: Bytes,
+        is_test: bool,
+        priority: u8,
+        refund_address: Address,
+        deadline: U256,
+        category: FixedBytes<4>,
+        encryption_meta: Bytes,
+    ) -> Result<U256, Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        self.send(sender, destination_chain, target, data, is_test, priority, refund_address, deadline, category, encryption_meta)
+    }
+
+    /// Gasless `send_message`: `sender` signs an EIP-712 `SendMessage` off-chain and
+    /// whoever submits it forwards the fee. `deadline` bounds the signature's validity.
+    #[payable]
+    pub fn send_message_with_sig(
+        &mut self,
+        sender: Address,
+        destination_chain: u32,
+        target: Address,
+        data: Bytes,
+        deadline: U256,
+        signature: Bytes,
+    ) -> Result<U256, Vec<u8>> {
+        if U256::from(self.vm().block_timestamp()) > deadline { return Err(enc(SignatureExpired { deadline })); }
+        let nonce = self.meta_nonces.get(sender);
+        let struct_hash = keccak(
+            (SEND_MESSAGE_TYPEHASH, sender, destination_chain, target, keccak(&data), deadline, nonce).abi_encode_params(),
+        );
+        let digest = keccak([b"\x19\x01".as_slice(), self.domain_separator().as_slice(), struct_hash.as_slice()].concat());
+        if self.recover_signer(digest, &signature) != Some(sender) { return Err(enc(InvalidSignature {})); }
+        self.meta_nonces.setter(sender).set(checked_add(nonce, U256::from(1u8))?);
+        self.send(sender, destination_chain, target, data, false, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, FixedBytes::ZERO, Bytes::new())
+    }
+
+    pub fn confirm_delivery(&mut self, message_id: U256, proof: Bytes) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let relayer = self.effective_relayer(caller);
+        let stake = self.check_relayer(caller, relayer)?;
+        self.check_confirmable(message_id, relayer, caller, &proof)?;
+        // Every check, proof included, precedes the first write: a rejected
+        // confirmation leaves no state behind and can simply be retried.
+        self.lock()?;
+        self.apply_confirmation(message_id, relayer, stake)?;
+        self.locked.set(false);
+        Ok(())
+    }
+
+    /// Confirms each message whose checks pass and returns the IDs that were
+    /// skipped, e.g. already confirmed or with a bad proof. Rewards are credited
+    /// together, so the relayer claims once with `claim_rewards`.
+    pub fn confirm_delivery_batch(&mut self, ids: Vec<U256>, proofs: Vec<Bytes>) -> Result<Vec<U256>, Vec<u8>> {
+        if ids.len() != proofs.len() { return Err(enc(LengthMismatch {})); }
+        let caller = self.vm().msg_sender();
+        let relayer = self.effective_relayer(caller);
+        let stake = self.check_relayer(caller, relayer)?;
+        self.lock()?;
+        let mut skipped = Vec::new();
+        for (id, proof) in ids.into_iter().zip(proofs) {
+            if self.check_confirmable(id, relayer, caller, &proof).is_err() { skipped.push(id); continue; }
+            self.apply_confirmation(id, relayer, stake)?;
+        }
+        self.locked.set(false);
+        Ok(skipped)
+    }
+
+    /// Reserves a pending message for the caller's relayer: for `exclusivity_window`
+    /// seconds no other relayer can confirm it. A relayer gets one claim per message;
+    /// once it lapses, the message is open to others.
+    pub fn claim_message(&mut self, message_id: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let relayer = self.effective_relayer(caller);
+        self.check_relayer(caller, relayer)?;
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        let status = m.status.get().to::<u8>();
+        if status != STATUS_PENDING { return Err(enc(WrongStatus { messageId: message_id, status })); }
+        let now = U256::from(self.vm().block_timestamp());
+        let holder = m.claimed_by.get();
+        if holder == relayer { return Err(enc(AlreadyClaimed { messageId: message_id })); }
+        if holder != Address::ZERO && now < m.claim_expiry.get() {
+            return Err(enc(MessageClaimedByOther { messageId: message_id, claimedBy: holder }));
+        }
+        self.check_deliverable(message_id, relayer, caller)?;
+        let expiry = checked_add(now, self.exclusivity_window.get())?;
+        { let mut m = self.messages.setter(message_id); m.claimed_by.set(relayer); m.claim_expiry.set(expiry); }
+        self.vm().log(MessageClaimed { messageId: message_id, relayer, expiry });
+        Ok(())
+    }
+
+    /// Lets the confirming relayer undo a mistaken confirmation within `revoke_window`.
+    /// The message returns to pending and its reward is clawed back from the relayer's
+    /// unclaimed balance, then from stake, which must stay at or above `min_stake`.
+    /// A burn already sent is not reversed and is not repeated on re-confirmation.
+    /// The protocol's share never left escrow, so the message is fully refundable again.
+    pub fn revoke_confirmation(&mut self, message_id: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let relayer = self.effective_relayer(caller);
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        let status = m.status.get().to::<u8>();
+        if status != STATUS_CONFIRMED { return Err(enc(WrongStatus { messageId: message_id, status })); }
+        if m.relayer.get() != relayer { return Err(enc(Unauthorized { caller })); }
+        let window = self.revoke_window.get();
+        if window == U256::ZERO || m.settled.get() || U256::from(self.vm().block_timestamp()) > checked_add(m.confirmed_at.get(), window)? {
+            return Err(enc(RevokeWindowClosed { messageId: message_id }));
+        }
+        let (reward, confirmed_at) = (m.reward.get(), m.confirmed_at.get());
+        let owed = self.pending_withdrawals.get(relayer);
+        let from_owed = owed.min(reward);
+        let from_stake = checked_sub(reward, from_owed)?;
+        let stake = self.relayers.getter(relayer).stake.get();
+        let required = checked_add(self.min_stake.get(), from_stake)?;
+        if stake < required { return Err(enc(InsufficientStake { required, provided: stake })); }
         {
-            let mut m = self.messages.setter(id);
-            m.sender.set(sender);
-            m.destination_chain.set(ck);
-            m.target.set(target);
-            m.timestamp.set(ts);
-            m.fee_paid.set(val);
+            let mut m = self.messages.setter(message_id);
             m.status.set(U8::from(STATUS_PENDING));
             m.relayer.set(Address::ZERO);
+            m.confirmed_at.set(U256::ZERO);
         }
-        self.protocol_fee_balance.set(self.protocol_fee_balance.get() + val);
-        self.vm().log(MessageSent { messageId: id, sender, destinationChain: destination_chain, target, data, fee: val });
-        Ok(id)
+        self.pending_withdrawals.setter(relayer).set(checked_sub(owed, from_owed)?);
+        self.total_claimable.set(checked_sub(self.total_claimable.get(), from_owed)?);
+        if from_stake > U256::ZERO {
+            self.relayers.setter(relayer).stake.set(checked_sub(stake, from_stake)?);
+            self.total_active_stake.set(checked_sub(self.total_active_stake.get(), from_stake)?);
+        }
+        let ok = checked_sub(self.relayers.getter(relayer).successful.get(), U256::from(1u8))?;
+        self.relayers.setter(relayer).successful.set(ok);
+        // Give back the rate-limit slot if the confirmation counted toward the current window.
+        let (start, count) = (self.relayers.getter(relayer).window_start.get(), self.relayers.getter(relayer).window_count.get());
+        if confirmed_at >= start && count > U256::ZERO {
+            self.relayers.setter(relayer).window_count.set(count - U256::from(1u8));
+        }
+        let stake = self.relayers.getter(relayer).stake.get();
+        self.vm().log(RelayerStatsUpdated { relayer, successful: ok, stake });
+        self.forget_relayed(relayer, message_id);
+        self.total_confirmed.set(checked_sub(self.total_confirmed.get(), U256::from(1u8))?);
+        let sender = self.messages.getter(message_id).sender.get();
+        let delivered = checked_sub(self.sender_confirmed.get(sender), U256::from(1u8))?;
+        self.sender_confirmed.setter(sender).set(delivered);
+        let ck = self.messages.getter(message_id).destination_chain.get();
+        let confirmed = checked_sub(self.chain_confirmed_count.get(ck), U256::from(1u8))?;
+        self.chain_confirmed_count.setter(ck).set(confirmed);
+        self.pending_escrow.set(checked_add(self.pending_escrow.get(), reward)?);
+        self.protocol_fee_balance.set(checked_add(self.protocol_fee_balance.get(), reward)?);
+        self.vm().log(ConfirmationRevoked { messageId: message_id, relayer, clawedBack: reward });
+        Ok(())
     }
 
-    pub fn confirm_delivery(&mut self, message_id: U256, _proof: Bytes) -> Result<(), Vec<u8>> {
-        let relayer = self.vm().msg_sender();
-        if !self.relayers.getter(relayer).active.get() { return Err(enc(RelayerNotActive { relayer })); }
-        let ts = self.messages.getter(message_id).timestamp.get();
-        if ts == U256::ZERO { return Err(enc(MessageNotFound { messageId: message_id })); }
-        let st = self.messages.getter(message_id).status.get().to::<u8>();
-        if st != STATUS_PENDING { return Err(enc(AlreadyRelayed { messageId: message_id })); }
-        let fee = self.messages.getter(message_id).fee_paid.get();
+    /// Moves a confirmed message's protocol share out of `pending_escrow` once its
+    /// revoke window has passed, making it available to `withdraw_fees`. Anyone may call it.
+    pub fn release_escrow(&mut self, message_id: U256) -> Result<(), Vec<u8>> {
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        let status = m.status.get().to::<u8>();
+        if status != STATUS_CONFIRMED || m.settled.get() { return Err(enc(WrongStatus { messageId: message_id, status })); }
+        let until = checked_add(m.confirmed_at.get(), self.revoke_window.get())?;
+        if U256::from(self.vm().block_timestamp()) <= until { return Err(enc(RevokeWindowOpen { messageId: message_id, until })); }
+        let amount = checked_sub(self.escrow_of(message_id)?, m.reward.get())?;
+        self.messages.setter(message_id).settled.set(true);
+        self.pending_escrow.set(checked_sub(self.pending_escrow.get(), amount)?);
+        self.vm().log(EscrowReleased { messageId: message_id, amount });
+        Ok(())
+    }
+
+    /// Corrects the target of a pending message. Delivery proofs are checked against
+    /// the stored target, so they must be produced for `new_target`.
+    pub fn update_target(&mut self, message_id: U256, new_target: Address) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        if new_target == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        if caller != m.sender.get() { return Err(enc(Unauthorized { caller })); }
+        let status = m.status.get().to::<u8>();
+        if status != STATUS_PENDING { return Err(enc(WrongStatus { messageId: message_id, status })); }
+        let (old, cfg) = (m.target.get(), self.config_key(m.destination_chain.get().to::<u32>()));
+        if self.supported_chains.getter(cfg).strict_target.get() && new_target == self.supported_chains.getter(cfg).receiver_address.get() {
+            return Err(enc(InvalidTarget { target: new_target }));
+        }
+        self.messages.setter(message_id).target.set(new_target);
+        self.vm().log(TargetUpdated { messageId: message_id, oldTarget: old, newTarget: new_target });
+        Ok(())
+    }
+
+    /// Adds `msg_value` to a pending message's fee. The relayer's reward grows by
+    /// its usual share of the added amount; no treasury cut is taken on top-ups.
+    #[payable]
+    pub fn bump_fee(&mut self, message_id: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let added = self.vm().msg_value();
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        if caller != m.sender.get() { return Err(enc(Unauthorized { caller })); }
+        let status = m.status.get().to::<u8>();
+        if status != STATUS_PENDING { return Err(enc(WrongStatus { messageId: message_id, status })); }
+        let extra = mul_div(added, U256::from(RELAYER_REWARD_BPS), U256::from(BPS_DENOMINATOR))?;
+        let total = checked_add(m.fee_paid.get(), added)?;
+        let reward = checked_add(m.reward.get(), extra)?;
+        let burn = if m.burned.get() {
+            m.burn.get()
+        } else {
+            checked_add(m.burn.get(), mul_div(checked_sub(added, extra)?, self.burn_bps.get(), U256::from(BPS_DENOMINATOR))?)?
+        };
+        { let mut m = self.messages.setter(message_id); m.fee_paid.set(total); m.reward.set(reward); m.burn.set(burn); }
+        self.pending_escrow.set(checked_add(self.pending_escrow.get(), added)?);
+        self.protocol_fee_balance.set(checked_add(self.protocol_fee_balance.get(), added)?);
+        self.vm().log(FeeBumped { messageId: message_id, added, newTotal: total });
+        Ok(())
+    }
+
+    /// Honest counterpart to `confirm_delivery` for a message whose execution reverted
+    /// on the destination. `failure_proof` must be signed by the chain's receiver key.
+    /// The relayer is not penalised; the fee, less the treasury's share, is credited
+    /// to the message's refund address for `claim_rewards`.
+    pub fn report_delivery_failure(&mut self, message_id: U256, failure_proof: Bytes) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let relayer = self.effective_relayer(caller);
+        self.check_relayer(caller, relayer)?;
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        if m.status.get().to::<u8>() != STATUS_PENDING { return Err(enc(AlreadyRelayed { messageId: message_id })); }
+        self.check_claim(message_id, relayer)?;
+        let chain_id = m.destination_chain.get().to::<u32>();
+        let digest = failure_digest(message_id, chain_id, m.target.get());
+        match self.recover_signer(digest, &failure_proof) {
+            Some(signer) if signer != relayer && self.is_receiver_key(chain_id, signer) => {}
+            _ => return Err(enc(InvalidProof {})),
+        }
+        let to = m.refund_address.get();
+        let amount = self.escrow_of(message_id)?;
         {
             let mut m = self.messages.setter(message_id);
-            m.status.set(U8::from(STATUS_CONFIRMED));
+            m.status.set(U8::from(STATUS_FAILED));
             m.relayer.set(relayer);
         }
-        let reward = fee * U256::from(RELAYER_REWARD_BPS) / U256::from(10_000u64);
-        self.protocol_fee_balance.set(self.protocol_fee_balance.get() - reward);
-        transfer_eth(self.vm(), relayer, reward).map_err(|_| enc(TransferFailed {}))?;
-        self.vm().log(MessageConfirmed { messageId: message_id, relayer, timestamp: U256::from(self.vm().block_timestamp()) });
+        self.pending_escrow.set(checked_sub(self.pending_escrow.get(), amount)?);
+        self.protocol_fee_balance.set(checked_sub(self.protocol_fee_balance.get(), amount)?);
+        self.credit(to, amount)?;
+        let failed = checked_add(self.chain_failed_count.get(U32::from(chain_id)), U256::from(1u8))?;
+        self.chain_failed_count.setter(U32::from(chain_id)).set(failed);
+        self.total_failed.set(checked_add(self.total_failed.get(), U256::from(1u8))?);
+        self.vm().log(DeliveryFailed { messageId: message_id, relayer });
+        self.vm().log(MessageRefunded { messageId: message_id, to, amount });
+        Ok(())
+    }
+
+    /// Credits the fee of a pending message whose destination chain has been disabled,
+    /// or whose delivery deadline has passed, to its refund address for `claim_rewards`.
+    /// The treasury's share, accrued at send time, is kept.
+    pub fn refund_message(&mut self, message_id: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let m = self.messages.getter(message_id);
+        if !m.exists.get() { return Err(enc(MessageNotFound { messageId: message_id })); }
+        if caller != m.sender.get() { return Err(enc(Unauthorized { caller })); }
+        let to = m.refund_address.get();
+        if !self.is_refundable(message_id) { return Err(enc(NotRefundable { messageId: message_id })); }
+        let amount = self.escrow_of(message_id)?;
+        self.messages.setter(message_id).status.set(U8::from(STATUS_FAILED));
+        self.pending_escrow.set(checked_sub(self.pending_escrow.get(), amount)?);
+        self.protocol_fee_balance.set(checked_sub(self.protocol_fee_balance.get(), amount)?);
+        let ck = self.messages.getter(message_id).destination_chain.get();
+        let failed = checked_add(self.chain_failed_count.get(ck), U256::from(1u8))?;
+        self.chain_failed_count.setter(ck).set(failed);
+        self.total_failed.set(checked_add(self.total_failed.get(), U256::from(1u8))?);
+        self.credit(to, amount)?;
+        self.vm().log(MessageRefunded { messageId: message_id, to, amount });
+        Ok(())
+    }
+
+    /// Pays out everything credited to the caller by confirmations and fee withdrawals.
+    pub fn claim_rewards(&mut self) -> Result<(), Vec<u8>> {
+        self.lock()?;
+        let a = self.vm().msg_sender();
+        let amount = self.pending_withdrawals.get(a);
+        self.pending_withdrawals.setter(a).set(U256::ZERO);
+        self.total_claimable.set(checked_sub(self.total_claimable.get(), amount)?);
+        transfer_eth(self.vm(), a, amount).map_err(|_| enc(TransferFailed {}))?;
+        self.vm().log(RewardsClaimed { account: a, amount });
+        self.locked.set(false);
         Ok(())
     }
 
@@ -142,54 +613,1105 @@ impl MessageHub {
         let r = self.vm().msg_sender();
         let v = self.vm().msg_value();
         let req = self.min_stake.get();
-        if v < req { return Err(enc(InsufficientStake { required: req, provided: v })); }
         let prev = self.relayers.getter(r).stake.get();
-        { let mut ri = self.relayers.setter(r); ri.active.set(true); ri.stake.set(prev + v); }
+        let total = checked_add(prev, v)?;
+        if total < req { return Err(enc(InsufficientStake { required: req, provided: total })); }
+        if self.operator_relayer.get(r) != Address::ZERO { return Err(enc(Unauthorized { caller: r })); }
+        let joining = !self.relayers.getter(r).active.get();
+        let (max, count) = (self.max_relayers.get(), self.active_relayer_count.get());
+        if joining && max != U256::ZERO && count >= max { return Err(enc(RelayerCapReached { maxRelayers: max })); }
+        self.lock()?;
+        if joining {
+            self.active_relayer_count.set(checked_add(count, U256::from(1u8))?);
+        }
+        if !self.relayers.getter(r).listed.get() {
+            self.relayers.setter(r).listed.set(true);
+            self.relayer_list.push(r);
+        }
+        { let mut ri = self.relayers.setter(r); ri.active.set(true); ri.stake.set(total); }
+        self.total_active_stake.set(checked_add(self.total_active_stake.get(), v)?);
         self.vm().log(RelayerRegistered { relayer: r, stake: v });
+        self.locked.set(false);
         Ok(())
     }
 
+    /// `register_relayer` and `set_operator` in one transaction, so a cold-wallet
+    /// relayer can stake and propose its hot-wallet operator together. Because
+    /// operators must consent, this only emits `OperatorProposed`: the operator can't
+    /// confirm anything, and `OperatorSet` isn't emitted, until it calls `accept_operator`.
+    #[payable]
+    pub fn register_relayer_with_operator(&mut self, operator: Address) -> Result<(), Vec<u8>> {
+        if operator == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        self.register_relayer()?;
+        self.set_operator(operator)
+    }
+
     pub fn exit_relayer(&mut self) -> Result<(), Vec<u8>> {
         let r = self.vm().msg_sender();
         if !self.relayers.getter(r).active.get() { return Err(enc(RelayerNotActive { relayer: r })); }
+        self.lock()?;
         let s = self.relayers.getter(r).stake.get();
         { let mut ri = self.relayers.setter(r); ri.stake.set(U256::ZERO); ri.active.set(false); }
+        self.total_active_stake.set(checked_sub(self.total_active_stake.get(), s)?);
+        self.active_relayer_count.set(checked_sub(self.active_relayer_count.get(), U256::from(1u8))?);
         transfer_eth(self.vm(), r, s).map_err(|_| enc(TransferFailed {}))?;
         self.vm().log(RelayerExited { relayer: r, returned: s });
+        self.locked.set(false);
+        Ok(())
+    }
+
+    /// Proposes `operator` to call `confirm_delivery` for the caller; rewards and
+    /// stats still accrue to the staked relayer. Takes effect once the operator
+    /// calls `accept_operator`, replacing any previous operator.
+    pub fn set_operator(&mut self, operator: Address) -> Result<(), Vec<u8>> {
+        let r = self.vm().msg_sender();
+        if !self.relayers.getter(r).active.get() { return Err(enc(RelayerNotActive { relayer: r })); }
+        if operator == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        if self.relayers.getter(operator).listed.get() { return Err(enc(Unauthorized { caller: r })); }
+        self.pending_operator.setter(r).set(operator);
+        self.vm().log(OperatorProposed { relayer: r, operator });
+        Ok(())
+    }
+
+    /// Completes `set_operator`: the caller agrees to act for `relayer`. An address
+    /// serves one relayer at a time and can never be a registered relayer itself.
+    pub fn accept_operator(&mut self, relayer: Address) -> Result<(), Vec<u8>> {
+        let op = self.vm().msg_sender();
+        if self.pending_operator.get(relayer) != op || op == Address::ZERO { return Err(enc(Unauthorized { caller: op })); }
+        if !self.relayers.getter(relayer).active.get() { return Err(enc(RelayerNotActive { relayer })); }
+        let bound = self.operator_relayer.get(op);
+        if self.relayers.getter(op).listed.get() || (bound != Address::ZERO && bound != relayer) {
+            return Err(enc(Unauthorized { caller: op }));
+        }
+        let prev = self.relayer_operator.get(relayer);
+        if prev != Address::ZERO { self.operator_relayer.setter(prev).set(Address::ZERO); }
+        self.pending_operator.setter(relayer).set(Address::ZERO);
+        self.relayer_operator.setter(relayer).set(op);
+        self.operator_relayer.setter(op).set(relayer);
+        self.vm().log(OperatorSet { relayer, operator: op });
+        Ok(())
+    }
+
+    pub fn revoke_operator(&mut self) -> Result<(), Vec<u8>> {
+        let r = self.vm().msg_sender();
+        let prev = self.relayer_operator.get(r);
+        if prev != Address::ZERO { self.operator_relayer.setter(prev).set(Address::ZERO); }
+        self.relayer_operator.setter(r).set(Address::ZERO);
+        self.pending_operator.setter(r).set(Address::ZERO);
+        self.vm().log(OperatorSet { relayer: r, operator: Address::ZERO });
+        Ok(())
+    }
+
+    pub fn withdraw_stake(&mut self, amount: U256) -> Result<(), Vec<u8>> {
+        let r = self.vm().msg_sender();
+        if !self.relayers.getter(r).active.get() { return Err(enc(RelayerNotActive { relayer: r })); }
+        let s = self.relayers.getter(r).stake.get();
+        let req = self.min_stake.get();
+        let left = s.saturating_sub(amount);
+        if amount > s || left < req { return Err(enc(InsufficientStake { required: req, provided: left })); }
+        self.lock()?;
+        self.relayers.setter(r).stake.set(left);
+        self.total_active_stake.set(checked_sub(self.total_active_stake.get(), amount)?);
+        transfer_eth(self.vm(), r, amount).map_err(|_| enc(TransferFailed {}))?;
+        self.vm().log(StakeDecreased { relayer: r, amount, newTotal: left });
+        self.locked.set(false);
         Ok(())
     }
 
+    /// First step of an ownership handover; `new_owner` completes it with `accept_ownership`.
+    /// Only the owner itself can start it, not other `ADMIN_ROLE` holders.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        if caller != self.owner.get() { return Err(enc(Unauthorized { caller })); }
+        if new_owner == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        self.pending_owner.set(new_owner);
+        self.vm().log(OwnershipTransferStarted { previousOwner: self.owner.get(), newOwner: new_owner });
+        Ok(())
+    }
+
+    /// Moves `owner` and the three roles granted at deployment to the pending owner,
+    /// revoking them from the outgoing owner.
+    pub fn accept_ownership(&mut self) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        if caller != self.pending_owner.get() || caller == Address::ZERO { return Err(enc(Unauthorized { caller })); }
+        let old = self.owner.get();
+        self.previous_owner.set(old);
+        self.owner.set(caller);
+        self.pending_owner.set(Address::ZERO);
+        for role in [ADMIN_ROLE, CHAIN_MANAGER_ROLE, FEE_MANAGER_ROLE] {
+            if self.roles.getter(old).get(role) {
+                self.roles.setter(old).setter(role).set(false);
+                self.vm().log(RoleRevoked { role, account: old, sender: caller });
+            }
+            if !self.roles.getter(caller).get(role) {
+                self.roles.setter(caller).setter(role).set(true);
+                self.vm().log(RoleGranted { role, account: caller, sender: caller });
+            }
+        }
+        self.vm().log(OwnershipTransferred { previousOwner: old, newOwner: caller });
+        Ok(())
+    }
+
+    pub fn grant_role(&mut self, account: Address, role: B256) -> Result<(), Vec<u8>> {
+        self.only_role(ADMIN_ROLE)?;
+        self.roles.setter(account).setter(role).set(true);
+        self.vm().log(RoleGranted { role, account, sender: self.vm().msg_sender() });
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, account: Address, role: B256) -> Result<(), Vec<u8>> {
+        self.only_role(ADMIN_ROLE)?;
+        self.roles.setter(account).setter(role).set(false);
+        self.vm().log(RoleRevoked { role, account, sender: self.vm().msg_sender() });
+        Ok(())
+    }
+
+    pub fn has_role(&self, account: Address, role: B256) -> bool {
+        self.roles.getter(account).get(role)
+    }
+
     pub fn add_chain(&mut self, chain_id: u32, receiver_address: Address, base_fee: U256) -> Result<(), Vec<u8>> {
-        self.only_owner()?;
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        self.store_chain(chain_id, receiver_address, base_fee)
+    }
+
+    /// `add_chain` with the fee given in gwei; stored in wei like every other fee.
+    pub fn add_chain_gwei(&mut self, chain_id: u32, receiver_address: Address, base_fee_gwei: u64) -> Result<(), Vec<u8>> {
+        self.add_chain(chain_id, receiver_address, gwei_to_wei(base_fee_gwei))
+    }
+
+    /// `add_chain` for many chains at once; any invalid entry reverts the whole batch.
+    pub fn add_chains_batch(&mut self, chain_ids: Vec<u32>, receivers: Vec<Address>, base_fees: Vec<U256>) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        if chain_ids.len() != receivers.len() || chain_ids.len() != base_fees.len() { return Err(enc(LengthMismatch {})); }
+        for ((chain_id, receiver), fee) in chain_ids.into_iter().zip(receivers).zip(base_fees) {
+            self.store_chain(chain_id, receiver, fee)?;
+        }
+        Ok(())
+    }
+
+    /// Updates only `base_fee`, leaving the receiver and other settings untouched.
+    pub fn set_chain_base_fee(&mut self, chain_id: u32, new_fee: U256) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        if !self.supported_chains.getter(ck).enabled.get() { return Err(enc(ChainNotSupported { chainId: chain_id })); }
+        let old = self.supported_chains.getter(ck).base_fee.get();
+        self.supported_chains.setter(ck).base_fee.set(new_fee);
+        self.vm().log(ChainFeeUpdated { chainId: chain_id, oldFee: old, newFee: new_fee });
+        Ok(())
+    }
+
+    pub fn set_chain_base_fee_gwei(&mut self, chain_id: u32, base_fee_gwei: u64) -> Result<(), Vec<u8>> {
+        self.set_chain_base_fee(chain_id, gwei_to_wei(base_fee_gwei))
+    }
+
+    /// Fallback terms for any chain ID that was never added with `add_chain`.
+    pub fn set_default_chain_config(&mut self, enabled: bool, receiver_address: Address, base_fee: U256) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
         if receiver_address == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        let mut c = self.supported_chains.setter(U32::from(DEFAULT_CHAIN_KEY));
+        c.enabled.set(enabled);
+        c.receiver_address.set(receiver_address);
+        c.base_fee.set(base_fee);
+        Ok(())
+    }
+
+    /// Re-enables a previously added chain, keeping its stored receiver and fee.
+    pub fn enable_chain(&mut self, chain_id: u32) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
         let ck = U32::from(chain_id);
-        { let mut c = self.supported_chains.setter(ck); c.enabled.set(true); c.receiver_address.set(receiver_address); c.base_fee.set(base_fee); }
-        self.vm().log(ChainAdded { chainId: chain_id, receiver: receiver_address, baseFee: base_fee });
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        self.supported_chains.setter(ck).enabled.set(true);
+        self.vm().log(ChainEnabled { chainId: chain_id });
+        Ok(())
+    }
+
+    pub fn disable_chain(&mut self, chain_id: u32) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        if !self.supported_chains.getter(ck).enabled.get() { return Err(enc(ChainNotSupported { chainId: chain_id })); }
+        self.supported_chains.setter(ck).enabled.set(false);
+        self.vm().log(ChainDisabled { chainId: chain_id });
+        Ok(())
+    }
+
+    /// Opt-in in-order delivery: a message can only be confirmed once the sender's
+    /// previous message to the same chain is confirmed, refunded or failed.
+    pub fn set_chain_ordered(&mut self, chain_id: u32, ordered: bool) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        self.supported_chains.setter(ck).ordered.set(ordered);
+        Ok(())
+    }
+
+    /// Shortest payload `send_message` accepts for `chain_id`, e.g. 4 for a selector; zero means none.
+    pub fn set_chain_min_data_len(&mut self, chain_id: u32, min_len: u32) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        self.supported_chains.setter(ck).min_data_len.set(U32::from(min_len));
+        Ok(())
+    }
+
+    /// Opt-in guard rejecting the chain's own receiver contract as a `target`.
+    pub fn set_chain_strict_target(&mut self, chain_id: u32, strict: bool) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        self.supported_chains.setter(ck).strict_target.set(strict);
+        Ok(())
+    }
+
+    /// Share of the protocol's cut of each fee sent to `BURN_ADDRESS` on confirmation.
+    /// The amount is fixed per message when it is sent.
+    pub fn set_burn_bps(&mut self, bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if bps > U256::from(BPS_DENOMINATOR) { return Err(enc(InvalidBps { bps })); }
+        self.burn_bps.set(bps);
+        Ok(())
+    }
+
+    /// Raising the minimum blocks under-collateralized relayers from confirming
+    /// until they top up via `register_relayer`.
+    pub fn set_min_stake(&mut self, min_stake: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if min_stake == U256::ZERO { return Err(enc(InvalidMinStake {})); }
+        self.min_stake.set(min_stake);
+        Ok(())
+    }
+
+    pub fn set_challenge_period(&mut self, period: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if period < U256::from(MIN_CHALLENGE_PERIOD) || period > U256::from(MAX_CHALLENGE_PERIOD) {
+            return Err(enc(InvalidChallengePeriod { period }));
+        }
+        let old = self.challenge_period.get();
+        self.challenge_period.set(period);
+        self.vm().log(ChallengePeriodUpdated { oldPeriod: old, newPeriod: period });
+        Ok(())
+    }
+
+    /// Sends the owner any ETH held beyond stake, fees, treasury and claimable
+    /// balances, e.g. forced sends. Accounted funds are never touched.
+    pub fn sweep_unaccounted(&mut self) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let (.., amount) = self.balance_breakdown()?;
+        if amount == U256::ZERO { return Err(enc(NothingToSweep {})); }
+        self.lock()?;
+        let to = self.owner.get();
+        transfer_eth(self.vm(), to, amount).map_err(|_| enc(TransferFailed {}))?;
+        self.vm().log(UnaccountedSwept { to, amount });
+        self.locked.set(false);
+        Ok(())
+    }
+
+    /// How long a credited balance may sit unclaimed before `escheat` can reclaim
+    /// it; at least 30 days, or zero to disable escheatment.
+    pub fn set_claim_deadline(&mut self, period: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let min = U256::from(MIN_CLAIM_DEADLINE);
+        if period != U256::ZERO && period < min { return Err(enc(InvalidClaimDeadline { period, min })); }
+        self.claim_deadline.set(period);
+        Ok(())
+    }
+
+    /// Returns `account`'s unclaimed balance to the protocol once `claim_deadline`
+    /// has passed since it was last credited.
+    pub fn escheat(&mut self, account: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let (amount, period) = (self.pending_withdrawals.get(account), self.claim_deadline.get());
+        let expiry = checked_add(self.accrued_at.get(account), period)?;
+        if amount == U256::ZERO || period == U256::ZERO || U256::from(self.vm().block_timestamp()) <= expiry {
+            return Err(enc(NotEscheatable { account }));
+        }
+        self.pending_withdrawals.setter(account).set(U256::ZERO);
+        self.total_claimable.set(checked_sub(self.total_claimable.get(), amount)?);
+        self.protocol_fee_balance.set(checked_add(self.protocol_fee_balance.get(), amount)?);
+        self.vm().log(Escheated { account, amount });
+        Ok(())
+    }
+
+    /// At most `max` confirmations per relayer in any `window`-second period; a zero
+    /// `max` or `window` means unlimited.
+    pub fn set_rate_limit(&mut self, max: U256, window: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.max_confirmations_per_window.set(max);
+        self.rate_window.set(window);
+        Ok(())
+    }
+
+    /// How long a `claim_message` reservation lasts; zero makes claims expire at once.
+    pub fn set_exclusivity_window(&mut self, window: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.exclusivity_window.set(window);
+        Ok(())
+    }
+
+    /// How long after confirming a relayer may call `revoke_confirmation`; zero disables it.
+    pub fn set_revoke_window(&mut self, window: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.revoke_window.set(window);
+        Ok(())
+    }
+
+    /// Caps how many relayers can be active at once; zero means unlimited.
+    pub fn set_max_relayers(&mut self, max: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.max_relayers.set(max);
+        Ok(())
+    }
+
+    /// Moves protocol fees into the calling fee manager's claimable balance. Fees
+    /// escrowed for pending messages, and for confirmations still inside the revoke
+    /// window, are excluded from what can be withdrawn.
+    pub fn withdraw_fees(&mut self, amount: U256) -> Result<(), Vec<u8>> {
+        self.only_role(FEE_MANAGER_ROLE)?;
+        let available = checked_sub(self.protocol_fee_balance.get(), self.pending_escrow.get())?;
+        if amount > available { return Err(enc(InsufficientFees { available, requested: amount })); }
+        self.protocol_fee_balance.set(checked_sub(self.protocol_fee_balance.get(), amount)?);
+        let to = self.vm().msg_sender();
+        self.credit(to, amount)?;
+        self.vm().log(FeesWithdrawn { to, amount });
+        Ok(())
+    }
+
+    /// Sends protocol fees straight to a successor contract when redeploying. Like
+    /// `withdraw_fees`, fees escrowed for pending messages stay behind.
+    pub fn migrate_fees(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if to == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        let available = checked_sub(self.protocol_fee_balance.get(), self.pending_escrow.get())?;
+        if amount > available { return Err(enc(InsufficientFees { available, requested: amount })); }
+        self.lock()?;
+        self.protocol_fee_balance.set(checked_sub(self.protocol_fee_balance.get(), amount)?);
+        transfer_eth(self.vm(), to, amount).map_err(|_| enc(TransferFailed {}))?;
+        self.vm().log(FeesMigrated { to, amount });
+        self.locked.set(false);
+        Ok(())
+    }
+
+    /// Global floor applied on top of every chain's `base_fee`.
+    pub fn set_min_fee(&mut self, min_fee: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.min_fee.set(min_fee);
+        Ok(())
+    }
+
+    /// Congestion multiplier applied to every chain's `base_fee`, capped at 5x;
+    /// zero restores the 1x default.
+    pub fn set_fee_multiplier(&mut self, bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let max = U256::from(MAX_FEE_MULTIPLIER_BPS);
+        if bps > max { return Err(enc(MultiplierTooHigh { bps, max })); }
+        self.fee_multiplier_bps.set(bps);
+        Ok(())
+    }
+
+    /// Fee multiplier for a priority tier, capped at 5x like the congestion multiplier;
+    /// zero restores the 1x default. The relayer reward is a share of the fee paid, so
+    /// it scales with the tier too.
+    pub fn set_priority_multiplier(&mut self, priority: u8, bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if priority > PRIORITY_FAST { return Err(enc(InvalidPriority { priority })); }
+        let max = U256::from(MAX_FEE_MULTIPLIER_BPS);
+        if bps > max { return Err(enc(MultiplierTooHigh { bps, max })); }
+        self.priority_multiplier_bps.setter(U8::from(priority)).set(bps);
+        Ok(())
+    }
+
+    /// Weight kept by the previous average when `average_fee` takes in a new fee;
+    /// higher values smooth more.
+    pub fn set_fee_ema_alpha(&mut self, bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if bps > U256::from(BPS_DENOMINATOR) { return Err(enc(InvalidBps { bps })); }
+        self.fee_ema_alpha_bps.set(bps);
+        Ok(())
+    }
+
+    pub fn set_treasury(&mut self, treasury: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if treasury == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        self.treasury.set(treasury);
+        Ok(())
+    }
+
+    /// Share of each fee credited to the treasury at send time. Bounded so the
+    /// remainder always covers the relayer reward.
+    pub fn set_treasury_bps(&mut self, bps: U256) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if bps > U256::from(BPS_DENOMINATOR - RELAYER_REWARD_BPS) { return Err(enc(InvalidBps { bps })); }
+        self.treasury_bps.set(bps);
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(&mut self) -> Result<(), Vec<u8>> {
+        let t = self.vm().msg_sender();
+        if t != self.treasury.get() || t == Address::ZERO { return Err(enc(Unauthorized { caller: t })); }
+        self.lock()?;
+        let amount = self.treasury_balance.get();
+        self.treasury_balance.set(U256::ZERO);
+        transfer_eth(self.vm(), t, amount).map_err(|_| enc(TransferFailed {}))?;
+        self.vm().log(TreasuryWithdrawn { treasury: t, amount });
+        self.locked.set(false);
+        Ok(())
+    }
+
+    /// Starts rotating a chain's receiver key. Proofs signed by either key verify
+    /// until `activation_time`; after that only `new_receiver` does.
+    pub fn rotate_chain_receiver(&mut self, chain_id: u32, new_receiver: Address) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        if new_receiver == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        let at = U256::from(self.vm().block_timestamp() + RECEIVER_ROTATION_GRACE);
+        { let mut c = self.supported_chains.setter(ck); c.pending_receiver.set(new_receiver); c.activation_time.set(at); }
+        self.vm().log(ChainReceiverRotated { chainId: chain_id, newReceiver: new_receiver, activationTime: at });
+        Ok(())
+    }
+
+    pub fn finalize_chain_receiver(&mut self, chain_id: u32) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        let ck = U32::from(chain_id);
+        let pending = self.supported_chains.getter(ck).pending_receiver.get();
+        let at = self.supported_chains.getter(ck).activation_time.get();
+        if pending == Address::ZERO || U256::from(self.vm().block_timestamp()) < at {
+            return Err(enc(RotationNotReady { chainId: chain_id, activationTime: at }));
+        }
+        {
+            let mut c = self.supported_chains.setter(ck);
+            c.receiver_address.set(pending);
+            c.pending_receiver.set(Address::ZERO);
+            c.activation_time.set(U256::ZERO);
+        }
+        self.vm().log(ChainReceiverFinalized { chainId: chain_id, receiver: pending });
+        Ok(())
+    }
+
+    /// Latest state root posted for `chain_id`, against which Merkle-mode proofs verify.
+    pub fn submit_state_root(&mut self, chain_id: u32, root: B256) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        self.chain_state_root.setter(U32::from(chain_id)).set(root);
+        self.vm().log(StateRootSubmitted { chainId: chain_id, root });
+        Ok(())
+    }
+
+    /// Selects how `confirm_delivery` proofs are checked for `chain_id`: signatures
+    /// (`0`, the default) or Merkle inclusion under the chain's state root (`1`).
+    pub fn set_proof_mode(&mut self, chain_id: u32, mode: u8) -> Result<(), Vec<u8>> {
+        self.only_role(CHAIN_MANAGER_ROLE)?;
+        if mode > PROOF_MERKLE { return Err(enc(InvalidProofMode { mode })); }
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        self.supported_chains.setter(ck).proof_mode.set(U8::from(mode));
+        Ok(())
+    }
+
+    /// When set, `confirm_delivery` only accepts a proof signed by the destination
+    /// chain's receiver key, and never one recovered to the confirming relayer.
+    pub fn set_require_third_party_proof(&mut self, required: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.require_third_party_proof.set(required);
         Ok(())
     }
 
     pub fn get_message_status(&self, id: U256) -> Result<u8, Vec<u8>> {
-        if self.messages.getter(id).timestamp.get() == U256::ZERO { return Err(enc(MessageNotFound { messageId: id })); }
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
         Ok(self.messages.getter(id).status.get().to::<u8>())
     }
 
-    pub fn calculate_fee(&self, destination_chain: u32) -> U256 {
-        self.supported_chains.getter(U32::from(destination_chain)).base_fee.get()
+    pub fn is_pending(&self, id: U256) -> Result<bool, Vec<u8>>   { Ok(self.get_message_status(id)? == STATUS_PENDING) }
+    pub fn is_confirmed(&self, id: U256) -> Result<bool, Vec<u8>> { Ok(self.get_message_status(id)? == STATUS_CONFIRMED) }
+    pub fn is_failed(&self, id: U256) -> Result<bool, Vec<u8>>    { Ok(self.get_message_status(id)? == STATUS_FAILED) }
+
+    pub fn is_test_message(&self, id: U256) -> Result<bool, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).is_test.get())
     }
 
+    /// Test-flagged message IDs in send order, at most `MAX_PAGE` per call.
+    pub fn get_test_messages(&self, offset: U256, limit: U256) -> Vec<U256> {
+        page(&self.test_messages, offset, limit)
+    }
+
+    /// Standard-priority fee for the caller, sender discount included.
+    pub fn calculate_fee(&self, destination_chain: u32) -> Result<U256, Vec<u8>> {
+        if !self.supported_chains.getter(self.config_key(destination_chain)).enabled.get() {
+            return Err(enc(ChainNotSupported { chainId: destination_chain }));
+        }
+        self.required_fee(self.vm().msg_sender(), destination_chain, PRIORITY_STANDARD)
+    }
+
+    /// Application-defined discriminator recorded at send time, for off-chain routing.
+    pub fn get_message_category(&self, id: U256) -> Result<FixedBytes<4>, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).category.get())
+    }
+
+    /// Opaque sender-supplied metadata (e.g. scheme and ephemeral key) for encrypted payloads.
+    pub fn get_message_encryption_meta(&self, id: U256) -> Result<Bytes, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(Bytes::from(self.messages.getter(id).encryption_meta.get_bytes()))
+    }
+
+    pub fn get_message_timestamp(&self, id: U256) -> Result<U256, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).timestamp.get())
+    }
+
+    pub fn get_message_target(&self, id: U256) -> Result<Address, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).target.get())
+    }
+
+    /// Latest timestamp `confirm_delivery` accepts for this message; zero means none.
+    pub fn get_message_deadline(&self, id: U256) -> Result<U256, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).deadline.get())
+    }
+
+    pub fn get_message_refund_address(&self, id: U256) -> Result<Address, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).refund_address.get())
+    }
+
+    /// Reward `confirm_delivery` pays for this message, fixed when it was sent.
+    pub fn relayer_reward_for(&self, id: U256) -> Result<U256, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).reward.get())
+    }
+
+    /// Position of the message among its sender's messages to the same chain, from 1.
+    pub fn get_message_sequence(&self, id: U256) -> Result<U256, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).sequence.get())
+    }
+
+    pub fn sender_nonce(&self, sender: Address, destination_chain: u32) -> U256 {
+        self.sender_dest_nonce.getter(sender).get(U32::from(destination_chain))
+    }
+
+    pub fn get_message_priority(&self, id: U256) -> Result<u8, Vec<u8>> {
+        if !self.messages.getter(id).exists.get() { return Err(enc(MessageNotFound { messageId: id })); }
+        Ok(self.messages.getter(id).priority.get().to::<u8>())
+    }
+
+    /// Exact `msg_value` `send_message` requires from the caller for these arguments,
+    /// including their sender discount; rejects what the send would reject. No fee
+    /// component depends on `data_len` today.
+    pub fn quote_fee(&self, destination_chain: u32, data_len: U256, priority: u8) -> Result<U256, Vec<u8>> {
+        if priority > PRIORITY_FAST { return Err(enc(InvalidPriority { priority })); }
+        let cfg = self.config_key(destination_chain);
+        if !self.supported_chains.getter(cfg).enabled.get() { return Err(enc(ChainNotSupported { chainId: destination_chain })); }
+        let min_len = self.supported_chains.getter(cfg).min_data_len.get().to::<u32>();
+        if data_len < U256::from(min_len) { return Err(enc(DataTooShort { min: min_len, actual: data_len })); }
+        self.required_fee(self.vm().msg_sender(), destination_chain, priority)
+    }
+
+    /// Total `msg_value` the caller needs to send one message per entry of `destinations`.
+    pub fn quote_batch_fee(&self, destinations: Vec<u32>, data_lens: Vec<U256>) -> Result<U256, Vec<u8>> {
+        if destinations.len() != data_lens.len() { return Err(enc(LengthMismatch {})); }
+        let mut total = U256::ZERO;
+        for (chain_id, data_len) in destinations.into_iter().zip(data_lens) {
+            total = checked_add(total, self.quote_fee(chain_id, data_len, PRIORITY_STANDARD)?)?;
+        }
+        Ok(total)
+    }
+
+    /// Messages confirmed by `relayer`, oldest first, at most `MAX_PAGE` per call.
+    pub fn messages_relayed_by(&self, relayer: Address, offset: U256, limit: U256) -> Vec<U256> {
+        page(&self.relayer_messages.getter(relayer), offset, limit)
+    }
+
+    /// Relayers ranked by successful confirmations, at most `MAX_LEADERBOARD` entries.
+    /// Reads and sorts the whole relayer list, so keep it to off-chain `eth_call`s.
+    pub fn top_relayers(&self, limit: U256) -> Vec<(Address, U256)> {
+        let mut all: Vec<(Address, U256)> = (0..self.relayer_list.len())
+            .filter_map(|i| self.relayer_list.get(i))
+            .map(|r| (r, self.relayers.getter(r).successful.get()))
+            .collect();
+        all.sort_by(|a, b| b.1.cmp(&a.1));
+        all.truncate(limit.min(U256::from(MAX_LEADERBOARD)).to::<usize>());
+        all
+    }
+
+    pub fn active_chains(&self) -> Vec<u32> {
+        (0..self.chain_ids.len())
+            .filter_map(|i| self.chain_ids.get(i))
+            .filter(|ck| self.supported_chains.getter(*ck).enabled.get())
+            .map(|ck| ck.to::<u32>())
+            .collect()
+    }
+
+    /// `(totalMessages, chainCount, feeBalance, minStake, challengePeriod, totalConfirmed,
+    /// totalFailed)` in one call.
+    pub fn protocol_stats(&self) -> (U256, U256, U256, U256, U256, U256, U256) {
+        (
+            self.message_nonce.get(),
+            self.chain_count.get(),
+            self.protocol_fee_balance.get(),
+            self.min_stake.get(),
+            self.challenge_period.get(),
+            self.total_confirmed.get(),
+            self.total_failed.get(),
+        )
+    }
+
+    /// Discount off the chain's base fee earned by `sender`'s confirmed messages.
+    pub fn sender_discount_bps(&self, sender: Address) -> U256 {
+        let delivered = self.sender_confirmed.get(sender);
+        SENDER_DISCOUNT_TIERS
+            .iter()
+            .find(|(min, _)| delivered >= U256::from(*min))
+            .map_or(U256::ZERO, |(_, bps)| U256::from(*bps))
+    }
+
+    /// `(sent, confirmed, failed)` message counts for one destination chain.
+    pub fn chain_stats(&self, chain_id: u32) -> (U256, U256, U256) {
+        let ck = U32::from(chain_id);
+        (self.chain_message_count.get(ck), self.chain_confirmed_count.get(ck), self.chain_failed_count.get(ck))
+    }
+
+    /// `(owner, minStake, challengePeriod, protocolFeeBalance, chainCount, messageNonce)`
+    /// for client setup. There is no pause switch, so no `paused` flag.
+    pub fn config(&self) -> (Address, U256, U256, U256, U256, U256) {
+        (
+            self.owner.get(),
+            self.min_stake.get(),
+            self.challenge_period.get(),
+            self.protocol_fee_balance.get(),
+            self.chain_count.get(),
+            self.message_nonce.get(),
+        )
+    }
+
+    /// `(totalStaked, protocolFees, treasuryFees, claimable, unaccounted)` where `unaccounted` is
+    /// the contract balance beyond the tracked buckets (zero if they exceed it). Reverts
+    /// if the buckets overflow rather than reporting accounted funds as unaccounted.
+    pub fn balance_breakdown(&self) -> Result<(U256, U256, U256, U256, U256), Vec<u8>> {
+        let staked = self.total_active_stake.get();
+        let fees = self.protocol_fee_balance.get();
+        let treasury = self.treasury_balance.get();
+        let claimable = self.total_claimable.get();
+        let balance = self.vm().balance(self.vm().contract_address());
+        let accounted = checked_add(checked_add(staked, fees)?, checked_add(treasury, claimable)?)?;
+        Ok((staked, fees, treasury, claimable, balance.saturating_sub(accounted)))
+    }
+
+    /// EIP-712 domain: `EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)`.
+    pub fn domain_separator(&self) -> B256 {
+        let chain_id = U256::from(self.vm().chain_id());
+        keccak((DOMAIN_TYPEHASH, keccak(DOMAIN_NAME), keccak(VERSION), chain_id, self.vm().contract_address()).abi_encode_params())
+    }
+
+    pub fn version(&self) -> String                     { String::from(VERSION) }
+    pub fn version_major_minor(&self) -> (u8, u8)       { (VERSION_MAJOR, VERSION_MINOR) }
     pub fn is_active_relayer(&self, r: Address) -> bool { self.relayers.getter(r).active.get() }
     pub fn message_count(&self) -> U256                 { self.message_nonce.get() }
     pub fn owner(&self) -> Address                      { self.owner.get() }
+    pub fn pending_owner(&self) -> Address              { self.pending_owner.get() }
+    pub fn previous_owner(&self) -> Address             { self.previous_owner.get() }
     pub fn min_stake(&self) -> U256                     { self.min_stake.get() }
+    pub fn min_fee(&self) -> U256                       { self.min_fee.get() }
+    pub fn challenge_period(&self) -> U256              { self.challenge_period.get() }
+    pub fn operator_of(&self, r: Address) -> Address    { self.relayer_operator.get(r) }
+    pub fn pending_operator(&self, r: Address) -> Address { self.pending_operator.get(r) }
+    pub fn priority_multiplier_bps(&self, p: u8) -> U256 { self.priority_multiplier(p) }
+    pub fn fee_multiplier_bps(&self) -> U256            { self.fee_multiplier() }
+    pub fn burn_bps(&self) -> U256                      { self.burn_bps.get() }
+    pub fn total_burned(&self) -> U256                  { self.total_burned.get() }
+    pub fn total_active_stake(&self) -> U256            { self.total_active_stake.get() }
+    pub fn max_relayers(&self) -> U256                  { self.max_relayers.get() }
+    pub fn active_relayer_count(&self) -> U256          { self.active_relayer_count.get() }
+    pub fn chain_count(&self) -> U256                   { self.chain_count.get() }
+    pub fn total_confirmed(&self) -> U256               { self.total_confirmed.get() }
+    pub fn total_failed(&self) -> U256                  { self.total_failed.get() }
+    pub fn pending_escrow(&self) -> U256                { self.pending_escrow.get() }
+    pub fn pending_withdrawal(&self, a: Address) -> U256 { self.pending_withdrawals.get(a) }
+    pub fn accrued_at(&self, a: Address) -> U256        { self.accrued_at.get(a) }
+    pub fn claim_deadline(&self) -> U256                { self.claim_deadline.get() }
+    pub fn revoke_window(&self) -> U256                 { self.revoke_window.get() }
+    pub fn exclusivity_window(&self) -> U256            { self.exclusivity_window.get() }
+    pub fn rate_limit(&self) -> (U256, U256)            { (self.max_confirmations_per_window.get(), self.rate_window.get()) }
+    pub fn sender_confirmed(&self, s: Address) -> U256  { self.sender_confirmed.get(s) }
+    pub fn meta_nonce(&self, sender: Address) -> U256   { self.meta_nonces.get(sender) }
+    pub fn messages_to_chain(&self, chain_id: u32) -> U256 { self.chain_message_count.get(U32::from(chain_id)) }
+    pub fn chain_state_root(&self, chain_id: u32) -> B256 { self.chain_state_root.get(U32::from(chain_id)) }
+    pub fn average_fee(&self, chain_id: u32) -> U256    { self.fee_ema.get(U32::from(chain_id)) }
+    pub fn fee_ema_alpha_bps(&self) -> U256             { self.fee_ema_alpha_bps.get() }
+    pub fn treasury(&self) -> Address                   { self.treasury.get() }
+    pub fn treasury_bps(&self) -> U256                  { self.treasury_bps.get() }
+    pub fn treasury_balance(&self) -> U256              { self.treasury_balance.get() }
 }
 
 impl MessageHub {
     fn only_owner(&self) -> Result<(), Vec<u8>> {
+        self.only_role(ADMIN_ROLE)
+    }
+
+    fn only_role(&self, role: B256) -> Result<(), Vec<u8>> {
         let c = self.vm().msg_sender();
-        if c != self.owner.get() { return Err(enc(Unauthorized { caller: c })); }
+        if !self.roles.getter(c).get(role) { return Err(enc(Unauthorized { caller: c })); }
         Ok(())
     }
-}
 
-#[cfg(feature = "export-abi")]
-pub fn export_abi_string() -> &'static str { "" }
+    /// Reentrancy guard taken by every entry point that sends ETH, and by
+    /// `register_relayer`, once its checks pass, and released just before it returns.
+    /// The SDK already rejects reentrant calls unless built with its `reentrant`
+    /// feature; this keeps them blocked if it is. An error return reverts the call,
+    /// so the flag never stays set.
+    fn lock(&mut self) -> Result<(), Vec<u8>> {
+        if self.locked.get() { return Err(enc(Reentrancy {})); }
+        self.locked.set(true);
+        Ok(())
+    }
+
+    /// What a pending message holds in `pending_escrow`: its fee less the treasury
+    /// cut and any burn already sent, enough to refund it in full or pay its reward
+    /// and burn on confirmation. A confirmed, unsettled message holds this less its reward.
+    fn escrow_of(&self, id: U256) -> Result<U256, Vec<u8>> {
+        let m = self.messages.getter(id);
+        let sent = if m.burned.get() { m.burn.get() } else { U256::ZERO };
+        checked_sub(m.fee_paid.get(), checked_add(m.treasury_cut.get(), sent)?)
+    }
+
+    /// Drops `id` from `relayer`'s confirmation history, keeping the rest in order.
+    /// Searches from the end, where a just-revoked confirmation sits.
+    fn forget_relayed(&mut self, relayer: Address, id: U256) {
+        let mut list = self.relayer_messages.setter(relayer);
+        let len = list.len();
+        let Some(i) = (0..len).rev().find(|&i| list.get(i) == Some(id)) else { return };
+        for j in i..len - 1 {
+            let next = list.get(j + 1).unwrap_or_default();
+            if let Some(mut slot) = list.setter(j) { slot.set(next); }
+        }
+        list.pop();
+    }
+
+    fn is_refundable(&self, id: U256) -> bool {
+        let m = self.messages.getter(id);
+        if m.status.get().to::<u8>() != STATUS_PENDING { return false; }
+        let deadline = m.deadline.get();
+        let expired = deadline != U256::ZERO && U256::from(self.vm().block_timestamp()) > deadline;
+        expired || !self.supported_chains.getter(self.config_key(m.destination_chain.get().to::<u32>())).enabled.get()
+    }
+
+    fn check_order(&self, id: U256) -> Result<(), Vec<u8>> {
+        let m = self.messages.getter(id);
+        let (ck, seq) = (m.destination_chain.get(), m.sequence.get());
+        let ordered = self.supported_chains.getter(self.config_key(ck.to::<u32>())).ordered.get();
+        if !ordered || seq <= U256::from(1u8) { return Ok(()); }
+        let prev = self.sequenced_ids.getter(m.sender.get()).getter(ck).get(checked_sub(seq, U256::from(1u8))?);
+        // A refunded or failed predecessor is resolved too; only a pending one blocks.
+        if self.messages.getter(prev).status.get().to::<u8>() == STATUS_PENDING {
+            return Err(enc(OutOfOrder { messageId: id, previousId: prev }));
+        }
+        Ok(())
+    }
+
+    fn credit(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let owed = checked_add(self.pending_withdrawals.get(to), amount)?;
+        self.pending_withdrawals.setter(to).set(owed);
+        self.accrued_at.setter(to).set(U256::from(self.vm().block_timestamp()));
+        self.total_claimable.set(checked_add(self.total_claimable.get(), amount)?);
+        Ok(())
+    }
+
+    fn effective_relayer(&self, caller: Address) -> Address {
+        let r = self.operator_relayer.get(caller);
+        if r == Address::ZERO { caller } else { r }
+    }
+
+    /// Key of the config governing `chain_id`: its own once added, otherwise the
+    /// default config stored under `DEFAULT_CHAIN_KEY`.
+    fn config_key(&self, chain_id: u32) -> U32 {
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO { U32::from(DEFAULT_CHAIN_KEY) } else { ck }
+    }
+
+    fn check_relayer(&self, caller: Address, relayer: Address) -> Result<U256, Vec<u8>> {
+        if !self.relayers.getter(relayer).active.get() { return Err(enc(RelayerNotActive { relayer: caller })); }
+        let (stake, req) = (self.relayers.getter(relayer).stake.get(), self.min_stake.get());
+        if stake < req { return Err(enc(InsufficientStake { required: req, provided: stake })); }
+        Ok(stake)
+    }
+
+    /// Checks shared by claiming and confirming: the deadline hasn't passed, the
+    /// destination is still enabled, and neither the relayer nor its operator sent it.
+    fn check_deliverable(&self, message_id: U256, relayer: Address, caller: Address) -> Result<(), Vec<u8>> {
+        let deadline = self.messages.getter(message_id).deadline.get();
+        if deadline != U256::ZERO && U256::from(self.vm().block_timestamp()) > deadline {
+            return Err(enc(DeliveryDeadlinePassed { messageId: message_id, deadline }));
+        }
+        let chain_id = self.messages.getter(message_id).destination_chain.get().to::<u32>();
+        if !self.supported_chains.getter(self.config_key(chain_id)).enabled.get() {
+            return Err(enc(ChainNotSupported { chainId: chain_id }));
+        }
+        let sender = self.messages.getter(message_id).sender.get();
+        if sender == relayer || sender == caller {
+            return Err(enc(SelfRelayForbidden { messageId: message_id }));
+        }
+        Ok(())
+    }
+
+    /// Rejects `relayer` while another relayer's `claim_message` reservation is live.
+    fn check_claim(&self, message_id: U256, relayer: Address) -> Result<(), Vec<u8>> {
+        let holder = self.messages.getter(message_id).claimed_by.get();
+        if holder != Address::ZERO && holder != relayer && U256::from(self.vm().block_timestamp()) < self.messages.getter(message_id).claim_expiry.get() {
+            return Err(enc(MessageClaimedByOther { messageId: message_id, claimedBy: holder }));
+        }
+        Ok(())
+    }
+
+    fn check_confirmable(&self, message_id: U256, relayer: Address, caller: Address, proof: &[u8]) -> Result<(), Vec<u8>> {
+        // IDs start at 1 and end at the nonce; anything outside is unknown regardless
+        // of what storage holds.
+        if message_id == U256::ZERO || message_id > self.message_nonce.get() {
+            return Err(enc(MessageNotFound { messageId: message_id }));
+        }
+        let st = self.messages.getter(message_id).status.get().to::<u8>();
+        if st != STATUS_PENDING { return Err(enc(AlreadyRelayed { messageId: message_id })); }
+        self.check_deliverable(message_id, relayer, caller)?;
+        self.check_claim(message_id, relayer)?;
+        self.verify_execution_proof(message_id, relayer, caller, proof)?;
+        self.check_order(message_id)?;
+        self.check_rate(relayer)
+    }
+
+    fn check_rate(&self, relayer: Address) -> Result<(), Vec<u8>> {
+        let (max, window) = (self.max_confirmations_per_window.get(), self.rate_window.get());
+        if max == U256::ZERO || window == U256::ZERO { return Ok(()); }
+        let r = self.relayers.getter(relayer);
+        let end = r.window_start.get().saturating_add(window);
+        if U256::from(self.vm().block_timestamp()) < end && r.window_count.get() >= max {
+            return Err(enc(RateLimited { relayer, windowEnd: end }));
+        }
+        Ok(())
+    }
+
+    fn apply_confirmation(&mut self, message_id: U256, relayer: Address, stake: U256) -> Result<(), Vec<u8>> {
+        let chain_id = self.messages.getter(message_id).destination_chain.get().to::<u32>();
+        let escrow = self.escrow_of(message_id)?;
+        let reward = self.messages.getter(message_id).reward.get();
+        let burn = if self.messages.getter(message_id).burned.get() { U256::ZERO } else { self.messages.getter(message_id).burn.get() };
+        // With a revoke window the protocol share stays escrowed until `release_escrow`,
+        // so a revoked message can still be refunded in full.
+        let settled = self.revoke_window.get() == U256::ZERO;
+        let released = if settled { escrow } else { checked_add(reward, burn)? };
+        {
+            let mut m = self.messages.setter(message_id);
+            m.status.set(U8::from(STATUS_CONFIRMED));
+            m.relayer.set(relayer);
+            m.confirmed_at.set(U256::from(self.vm().block_timestamp()));
+            m.burned.set(true);
+            m.settled.set(settled);
+        }
+        let ok = checked_add(self.relayers.getter(relayer).successful.get(), U256::from(1u8))?;
+        self.relayers.setter(relayer).successful.set(ok);
+        let now = U256::from(self.vm().block_timestamp());
+        let (start, count) = (self.relayers.getter(relayer).window_start.get(), self.relayers.getter(relayer).window_count.get());
+        if now >= start.saturating_add(self.rate_window.get()) {
+            let mut ri = self.relayers.setter(relayer); ri.window_start.set(now); ri.window_count.set(U256::from(1u8));
+        } else {
+            self.relayers.setter(relayer).window_count.set(checked_add(count, U256::from(1u8))?);
+        }
+        self.vm().log(RelayerStatsUpdated { relayer, successful: ok, stake });
+        self.total_confirmed.set(checked_add(self.total_confirmed.get(), U256::from(1u8))?);
+        let sender = self.messages.getter(message_id).sender.get();
+        let delivered = checked_add(self.sender_confirmed.get(sender), U256::from(1u8))?;
+        self.sender_confirmed.setter(sender).set(delivered);
+        let confirmed = checked_add(self.chain_confirmed_count.get(U32::from(chain_id)), U256::from(1u8))?;
+        self.chain_confirmed_count.setter(U32::from(chain_id)).set(confirmed);
+        self.relayer_messages.setter(relayer).push(message_id);
+        self.pending_escrow.set(checked_sub(self.pending_escrow.get(), released)?);
+        self.protocol_fee_balance.set(checked_sub(self.protocol_fee_balance.get(), checked_add(reward, burn)?)?);
+        self.credit(relayer, reward)?;
+        if burn > U256::ZERO {
+            self.total_burned.set(checked_add(self.total_burned.get(), burn)?);
+            transfer_eth(self.vm(), BURN_ADDRESS, burn).map_err(|_| enc(TransferFailed {}))?;
+        }
+        self.vm().log(MessageConfirmed { messageId: message_id, relayer, timestamp: U256::from(self.vm().block_timestamp()) });
+        Ok(())
+    }
+
+    /// Merkle-mode chains need `proof` to include `keccak256(abi.encode(messageId,
+    /// destinationChain, target))` under the chain's state root. Otherwise a
+    /// receiver signature is required only when `require_third_party_proof` is set.
+    fn verify_execution_proof(&self, message_id: U256, relayer: Address, caller: Address, proof: &[u8]) -> Result<(), Vec<u8>> {
+        let m = self.messages.getter(message_id);
+        let chain_id = m.destination_chain.get().to::<u32>();
+        let mode = self.supported_chains.getter(self.config_key(chain_id)).proof_mode.get().to::<u8>();
+        if mode == PROOF_MERKLE {
+            let root = self.chain_state_root.get(U32::from(chain_id));
+            let leaf = keccak((message_id, chain_id, m.target.get()).abi_encode_params());
+            if root == B256::ZERO || !verify_merkle(root, leaf, proof) { return Err(enc(InvalidProof {})); }
+            return Ok(());
+        }
+        if mode == PROOF_SIGNATURE && self.require_third_party_proof.get() {
+            let digest = delivery_digest(message_id, chain_id, m.target.get());
+            match self.recover_signer(digest, proof) {
+                Some(signer) if signer != relayer && signer != caller && self.is_receiver_key(chain_id, signer) => {}
+                _ => return Err(enc(InvalidProof {})),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_receiver_key(&self, chain_id: u32, signer: Address) -> bool {
+        let c = self.supported_chains.getter(self.config_key(chain_id));
+        let pending = c.pending_receiver.get();
+        if pending == Address::ZERO { return signer == c.receiver_address.get(); }
+        let grace = U256::from(self.vm().block_timestamp()) < c.activation_time.get();
+        signer == pending || (grace && signer == c.receiver_address.get())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn send(
+        &mut self,
+        sender: Address,
+        destination_chain: u32,
+        target: Address,
+        data: Bytes,
+        is_test: bool,
+        priority: u8,
+        refund_address: Address,
+        deadline: U256,
+        category: FixedBytes<4>,
+        encryption_meta: Bytes,
+    ) -> Result<U256, Vec<u8>> {
+        if target == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        if encryption_meta.len() > MAX_ENCRYPTION_META {
+            return Err(enc(EncryptionMetaTooLong { length: U256::from(encryption_meta.len()), max: U256::from(MAX_ENCRYPTION_META) }));
+        }
+        if priority > PRIORITY_FAST { return Err(enc(InvalidPriority { priority })); }
+        if deadline != U256::ZERO && deadline <= U256::from(self.vm().block_timestamp()) {
+            return Err(enc(InvalidDeadline { deadline }));
+        }
+        let ck = U32::from(destination_chain);
+        let cfg = self.config_key(destination_chain);
+        if !self.supported_chains.getter(cfg).enabled.get() {
+            return Err(enc(ChainNotSupported { chainId: destination_chain }));
+        }
+        if self.supported_chains.getter(cfg).strict_target.get() && target == self.supported_chains.getter(cfg).receiver_address.get() {
+            return Err(enc(InvalidTarget { target }));
+        }
+        let min_len = self.supported_chains.getter(cfg).min_data_len.get().to::<u32>();
+        if (data.len() as u64) < u64::from(min_len) { return Err(enc(DataTooShort { min: min_len, actual: U256::from(data.len()) })); }
+        let req = self.required_fee(sender, destination_chain, priority)?;
+        let val = self.vm().msg_value();
+        if val < req { return Err(enc(InsufficientFee { required: req, provided: val })); }
+        let id = checked_add(self.message_nonce.get(), U256::from(1u8))?;
+        let reward = mul_div(val, U256::from(RELAYER_REWARD_BPS), U256::from(BPS_DENOMINATOR))?;
+        let cut = mul_div(val, self.treasury_bps.get(), U256::from(BPS_DENOMINATOR))?;
+        let burn = mul_div(checked_sub(val, checked_add(reward, cut)?)?, self.burn_bps.get(), U256::from(BPS_DENOMINATOR))?;
+        self.message_nonce.set(id);
+        let ts = U256::from(self.vm().block_timestamp());
+        {
+            let mut m = self.messages.setter(id);
+            m.exists.set(true);
+            m.sender.set(sender);
+            m.destination_chain.set(ck);
+            m.target.set(target);
+            m.timestamp.set(ts);
+            m.fee_paid.set(val);
+            m.status.set(U8::from(STATUS_PENDING));
+            m.relayer.set(Address::ZERO);
+            m.is_test.set(is_test);
+            m.priority.set(U8::from(priority));
+            m.refund_address.set(if refund_address == Address::ZERO { sender } else { refund_address });
+            m.deadline.set(deadline);
+            m.category.set(category);
+            m.reward.set(reward);
+            m.burn.set(burn);
+        }
+        self.pending_escrow.set(checked_add(self.pending_escrow.get(), checked_sub(val, cut)?)?);
+        let seq = checked_add(self.sender_dest_nonce.getter(sender).get(ck), U256::from(1u8))?;
+        self.sender_dest_nonce.setter(sender).setter(ck).set(seq);
+        self.sequenced_ids.setter(sender).setter(ck).setter(seq).set(id);
+        self.messages.setter(id).sequence.set(seq);
+        if is_test { self.test_messages.push(id); }
+        let sent = checked_add(self.chain_message_count.get(ck), U256::from(1u8))?;
+        self.chain_message_count.setter(ck).set(sent);
+        self.update_fee_ema(ck, val)?;
+        if cut > U256::ZERO {
+            self.messages.setter(id).treasury_cut.set(cut);
+            self.treasury_balance.set(checked_add(self.treasury_balance.get(), cut)?);
+            self.vm().log(TreasuryFeeAccrued { messageId: id, amount: cut });
+        }
+        self.protocol_fee_balance.set(checked_add(self.protocol_fee_balance.get(), checked_sub(val, cut)?)?);
+        self.vm().log(MessageSent { messageId: id, sender, destinationChain: destination_chain, target, data, fee: val, isTest: is_test, category });
+        if !encryption_meta.is_empty() {
+            self.messages.setter(id).encryption_meta.set_bytes(&encryption_meta);
+            self.vm().log(EncryptionMetaAttached { messageId: id, meta: encryption_meta });
+        }
+        Ok(id)
+    }
+
+    /// `ema = ema * alpha + fee * (1 - alpha)`, seeded with the first fee seen.
+    fn update_fee_ema(&mut self, ck: U32, fee: U256) -> Result<(), Vec<u8>> {
+        let (d, alpha, prev) = (U256::from(BPS_DENOMINATOR), self.fee_ema_alpha_bps.get(), self.fee_ema.get(ck));
+        let ema = if prev == U256::ZERO { fee } else { checked_add(mul_div(prev, alpha, d)?, mul_div(fee, checked_sub(d, alpha)?, d)?)? };
+        self.fee_ema.setter(ck).set(ema);
+        Ok(())
+    }
+
+    fn store_chain(&mut self, chain_id: u32, receiver_address: Address, base_fee: U256) -> Result<(), Vec<u8>> {
+        if chain_id == DEFAULT_CHAIN_KEY { return Err(enc(ChainNotSupported { chainId: chain_id })); }
+        if u64::from(chain_id) == self.vm().chain_id() { return Err(enc(CannotRegisterLocalChain { chainId: chain_id })); }
+        if receiver_address == Address::ZERO { return Err(enc(ZeroAddress {})); }
+        let ck = U32::from(chain_id);
+        if self.supported_chains.getter(ck).receiver_address.get() == Address::ZERO {
+            self.chain_count.set(checked_add(self.chain_count.get(), U256::from(1u8))?);
+            self.chain_ids.push(ck);
+        }
+        { let mut c = self.supported_chains.setter(ck); c.enabled.set(true); c.receiver_address.set(receiver_address); c.base_fee.set(base_fee); }
+        self.vm().log(ChainAdded { chainId: chain_id, receiver: receiver_address, baseFee: base_fee });
+        Ok(())
+    }
+
+    /// The sender discount applies before the `min_fee` floor, so the floor always holds.
+    fn required_fee(&self, sender: Address, destination_chain: u32, priority: u8) -> Result<U256, Vec<u8>> {
+        let d = U256::from(BPS_DENOMINATOR);
+        let base = self.supported_chains.getter(self.config_key(destination_chain)).base_fee.get();
+        let discounted = mul_div(base, checked_sub(d, self.sender_discount_bps(sender))?, d)?;
+        let scaled = mul_div(discounted, self.fee_multiplier(), d)?.max(self.min_fee.get());
+        mul_div(scaled, self.priority_multiplier(priority), d)
+    }
+
+    fn fee_multiplier(&self) -> U256 {
+        let bps = self.fee_multiplier_bps.get();
+        if bps == U256::ZERO { U256::from(BPS_DENOMINATOR) } else { bps }
+    }
+
+    fn priority_multiplier(&self, priority: u8) -> U256 {
+        let bps = self.priority_multiplier_bps.get(U8::from(priority));
+        if bps == U256::ZERO { U256::from(BPS_DENOMINATOR) } else { bps }
+    }
+
+    /// Recovers the signer of a 65-byte `r || s || v` signature via the ecrecover precompile.
+    fn recover_signer(&self, digest: B256, sig: &[u8]) -> Option<Address> {
+        if sig.len() != 65 { return None; }
+        let mut v = sig[64];
+        if v < 27 { v += 27; }
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(digest.as_slice());
+        input[63] = v;
+        input[64..].copy_from_slice(&sig[..64]);
+        let out = static_call(self.vm(), Call::new(), ECRECOVER, &input).ok()?;
+        if out.len() != 32 { return None; }
+        let signer = Address::from_slice(&out[12..]);
+        (signer != Address::ZERO).then_some(signer)
+    }
+}
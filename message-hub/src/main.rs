@@ -6,5 +6,5 @@ pub extern "C" fn main() {}
 
 #[cfg(feature = "export-abi")]
 fn main() {
-    let _ = message_hub::export_abi_string();
+    message_hub::print_from_args();
 }
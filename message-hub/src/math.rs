@@ -0,0 +1,60 @@
+//! Checked `U256` arithmetic that reverts with `ArithmeticOverflow` instead of wrapping.
+
+use alloc::vec::Vec;
+use stylus_sdk::alloy_primitives::{U256, U512};
+
+use crate::{enc, ArithmeticOverflow};
+
+pub fn checked_add(a: U256, b: U256) -> Result<U256, Vec<u8>> {
+    a.checked_add(b).ok_or_else(|| enc(ArithmeticOverflow {}))
+}
+
+pub fn checked_sub(a: U256, b: U256) -> Result<U256, Vec<u8>> {
+    a.checked_sub(b).ok_or_else(|| enc(ArithmeticOverflow {}))
+}
+
+/// `a * b / d` rounded down. The product is taken in 512 bits, so only a zero
+/// `d` or a quotient that does not fit in 256 bits reverts.
+pub fn mul_div(a: U256, b: U256, d: U256) -> Result<U256, Vec<u8>> {
+    if d == U256::ZERO { return Err(enc(ArithmeticOverflow {})); }
+    if let Some(p) = a.checked_mul(b) { return Ok(p / d); }
+    let q = U512::from(a) * U512::from(b) / U512::from(d);
+    if q > U512::from(U256::MAX) { return Err(enc(ArithmeticOverflow {})); }
+    Ok(q.to::<U256>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overflow() -> Vec<u8> { enc(ArithmeticOverflow {}) }
+
+    #[test]
+    fn add_and_sub_at_the_bounds() {
+        assert_eq!(checked_add(U256::MAX - U256::from(1), U256::from(1)), Ok(U256::MAX));
+        assert_eq!(checked_add(U256::MAX, U256::from(1)), Err(overflow()));
+        assert_eq!(checked_sub(U256::from(1), U256::from(1)), Ok(U256::ZERO));
+        assert_eq!(checked_sub(U256::ZERO, U256::from(1)), Err(overflow()));
+    }
+
+    #[test]
+    fn mul_div_rounds_down_and_rejects_zero_divisor() {
+        assert_eq!(mul_div(U256::from(10), U256::from(8_000), U256::from(10_000)), Ok(U256::from(8)));
+        assert_eq!(mul_div(U256::from(7), U256::from(1), U256::from(2)), Ok(U256::from(3)));
+        assert_eq!(mul_div(U256::from(1), U256::from(1), U256::ZERO), Err(overflow()));
+    }
+
+    #[test]
+    fn mul_div_handles_an_overflowing_product_with_a_fitting_result() {
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX), Ok(U256::MAX));
+        // `(a % d) * b` overflows here even though the result fits.
+        let d = U256::MAX - U256::from(1);
+        assert_eq!(mul_div(d, U256::MAX, U256::MAX), Ok(d));
+        assert_eq!(mul_div(U256::MAX, U256::from(2), U256::from(2)), Ok(U256::MAX));
+    }
+
+    #[test]
+    fn mul_div_rejects_a_result_that_does_not_fit() {
+        assert_eq!(mul_div(U256::MAX, U256::from(2), U256::from(1)), Err(overflow()));
+    }
+}
@@ -0,0 +1,1371 @@
+//! Behaviour tests against the SDK's `TestVM`. Precompile and ETH-transfer calls
+//! are mocked; signatures are opaque bytes whose recovery is mocked per digest.
+
+use super::*;
+use alloy_sol_types::SolEvent;
+use stylus_sdk::testing::*;
+
+const OWNER: Address = address!("0000000000000000000000000000000000000a11");
+const SENDER: Address = address!("0000000000000000000000000000000000000b22");
+const RELAYER: Address = address!("0000000000000000000000000000000000000c33");
+const RECEIVER: Address = address!("0000000000000000000000000000000000000d44");
+const TARGET: Address = address!("0000000000000000000000000000000000000e55");
+const CHAIN: u32 = 10;
+const FEE: u64 = 1_000_000;
+const MIN_STAKE: u64 = 1_000_000_000;
+const PERIOD: u64 = 3_600;
+const NOW: u64 = 1_000_000;
+
+fn u(v: u64) -> U256 { U256::from(v) }
+
+/// Hub initialized by `OWNER`, with `CHAIN` added and `RELAYER` staked at the minimum.
+fn setup() -> (TestVM, MessageHub) {
+    let vm = TestVM::default();
+    vm.set_block_timestamp(NOW);
+    let mut c = MessageHub::from(&vm);
+    vm.set_sender(OWNER);
+    c.initialize(u(MIN_STAKE), u(PERIOD)).unwrap();
+    c.add_chain(CHAIN, RECEIVER, u(FEE)).unwrap();
+    register(&vm, &mut c, RELAYER, MIN_STAKE);
+    (vm, c)
+}
+
+fn register(vm: &TestVM, c: &mut MessageHub, relayer: Address, stake: u64) {
+    vm.set_sender(relayer);
+    vm.set_value(u(stake));
+    c.register_relayer().unwrap();
+    vm.set_value(U256::ZERO);
+}
+
+fn send_with(vm: &TestVM, c: &mut MessageHub, value: u64, data: &[u8]) -> Result<U256, Vec<u8>> {
+    vm.set_sender(SENDER);
+    vm.set_value(u(value));
+    let r = c.send_message(CHAIN, TARGET, Bytes::copy_from_slice(data));
+    vm.set_value(U256::ZERO);
+    r
+}
+
+fn send(vm: &TestVM, c: &mut MessageHub) -> U256 { send_with(vm, c, FEE, &[]).unwrap() }
+
+fn confirm(vm: &TestVM, c: &mut MessageHub, id: U256) -> Result<(), Vec<u8>> {
+    vm.set_sender(RELAYER);
+    c.confirm_delivery(id, Bytes::new())
+}
+
+/// Lets the hub send `amount` wei to `to`.
+fn allow_transfer(vm: &TestVM, to: Address, amount: U256) {
+    vm.mock_call(to, Vec::new(), amount, Ok(Vec::new()));
+}
+
+/// Makes `recover_signer(digest, sig)` return `signer`.
+fn mock_signer(vm: &TestVM, digest: B256, sig: &[u8], signer: Address) {
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(digest.as_slice());
+    input[63] = if sig[64] < 27 { sig[64] + 27 } else { sig[64] };
+    input[64..].copy_from_slice(&sig[..64]);
+    vm.mock_static_call(ECRECOVER, input.to_vec(), Ok(signer.into_word().to_vec()));
+}
+
+fn sig(tag: u8) -> Bytes { Bytes::from([vec![tag; 64], vec![27u8]].concat()) }
+
+fn emitted<E: SolEvent>(vm: &TestVM) -> bool {
+    vm.get_emitted_logs().iter().any(|(topics, _)| topics.first() == Some(&E::SIGNATURE_HASH))
+}
+
+#[test]
+fn setup_registers_chain_and_relayer() {
+    let (_vm, c) = setup();
+    assert_eq!(c.owner(), OWNER);
+    assert_eq!(c.active_chains(), vec![CHAIN]);
+    assert!(c.is_active_relayer(RELAYER));
+}
+
+#[test]
+fn burn_is_fixed_at_send_and_escrowed_from_withdrawals() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_burn_bps(u(5_000)).unwrap();
+    let id = send(&vm, &mut c);
+    // (fee - reward) * 50%, where the reward is 80% of the fee.
+    let burn = u(100_000);
+    vm.set_sender(OWNER);
+    c.set_burn_bps(U256::ZERO).unwrap();
+    assert_eq!(c.withdraw_fees(u(1)), Err(enc(InsufficientFees { available: U256::ZERO, requested: u(1) })));
+    allow_transfer(&vm, BURN_ADDRESS, burn);
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(c.total_burned(), burn);
+    assert_eq!(c.pending_withdrawal(RELAYER), u(800_000));
+    assert_eq!(c.protocol_fee_balance.get(), u(100_000));
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+}
+
+#[test]
+fn refund_is_paid_in_full_after_fees_are_withdrawn() {
+    let (vm, mut c) = setup();
+    let done = send(&vm, &mut c);
+    let pending = send(&vm, &mut c);
+    confirm(&vm, &mut c, done).unwrap();
+    vm.set_sender(OWNER);
+    assert_eq!(c.pending_escrow(), u(FEE));
+    c.withdraw_fees(u(200_000)).unwrap();
+    assert_eq!(c.withdraw_fees(u(1)), Err(enc(InsufficientFees { available: U256::ZERO, requested: u(1) })));
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(SENDER);
+    c.refund_message(pending).unwrap();
+    assert_eq!(c.get_message_status(pending), Ok(STATUS_FAILED));
+    assert_eq!(c.pending_withdrawal(SENDER), u(FEE));
+    assert_eq!(c.protocol_fee_balance.get(), U256::ZERO);
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+}
+
+#[test]
+fn refund_credits_the_refund_address() {
+    let (vm, mut c) = setup();
+    let payee = address!("0000000000000000000000000000000000000f66");
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let id = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, payee, U256::ZERO, FixedBytes::ZERO, Bytes::new()).unwrap();
+    vm.set_value(U256::ZERO);
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(SENDER);
+    c.refund_message(id).unwrap();
+    assert_eq!(c.pending_withdrawal(payee), u(FEE));
+    assert_eq!(c.pending_withdrawal(SENDER), U256::ZERO);
+    allow_transfer(&vm, payee, u(FEE));
+    vm.set_sender(payee);
+    c.claim_rewards().unwrap();
+    assert_eq!(c.pending_withdrawal(payee), U256::ZERO);
+}
+
+#[test]
+fn reported_failure_credits_the_sender_even_after_withdrawals() {
+    let (vm, mut c) = setup();
+    let done = send(&vm, &mut c);
+    let failed = send(&vm, &mut c);
+    confirm(&vm, &mut c, done).unwrap();
+    vm.set_sender(OWNER);
+    c.withdraw_fees(u(200_000)).unwrap();
+    let proof = sig(1);
+    mock_signer(&vm, failure_digest(failed, CHAIN, TARGET), &proof, RECEIVER);
+    vm.set_sender(RELAYER);
+    c.report_delivery_failure(failed, proof).unwrap();
+    assert_eq!(c.get_message_status(failed), Ok(STATUS_FAILED));
+    assert_eq!(c.pending_withdrawal(SENDER), u(FEE));
+    assert_eq!(c.relayers.getter(RELAYER).stake.get(), u(MIN_STAKE));
+    assert!(emitted::<DeliveryFailed>(&vm));
+}
+
+#[test]
+fn failure_report_respects_stake_and_claims() {
+    let (vm, mut c) = setup();
+    let other = address!("0000000000000000000000000000000000000f66");
+    register(&vm, &mut c, other, MIN_STAKE);
+    vm.set_sender(OWNER);
+    c.set_exclusivity_window(u(60)).unwrap();
+    let id = send(&vm, &mut c);
+    let proof = sig(1);
+    mock_signer(&vm, failure_digest(id, CHAIN, TARGET), &proof, RECEIVER);
+    vm.set_sender(other);
+    c.claim_message(id).unwrap();
+    vm.set_sender(RELAYER);
+    assert_eq!(c.report_delivery_failure(id, proof.clone()), Err(enc(MessageClaimedByOther { messageId: id, claimedBy: other })));
+    vm.set_sender(OWNER);
+    c.set_min_stake(u(2 * MIN_STAKE)).unwrap();
+    vm.set_sender(other);
+    assert_eq!(c.report_delivery_failure(id, proof.clone()), Err(enc(InsufficientStake { required: u(2 * MIN_STAKE), provided: u(MIN_STAKE) })));
+    vm.set_sender(OWNER);
+    c.set_min_stake(u(MIN_STAKE)).unwrap();
+    vm.set_sender(other);
+    c.report_delivery_failure(id, proof).unwrap();
+    assert_eq!(c.get_message_status(id), Ok(STATUS_FAILED));
+}
+
+#[test]
+fn failure_report_needs_a_receiver_signature() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    let proof = sig(2);
+    mock_signer(&vm, failure_digest(id, CHAIN, TARGET), &proof, RELAYER);
+    vm.set_sender(RELAYER);
+    assert_eq!(c.report_delivery_failure(id, proof), Err(enc(InvalidProof {})));
+    assert_eq!(c.get_message_status(id), Ok(STATUS_PENDING));
+}
+
+#[test]
+fn revoke_is_limited_to_the_window() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let (a, b) = (send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, a).unwrap();
+    confirm(&vm, &mut c, b).unwrap();
+    vm.set_block_timestamp(NOW + 600);
+    vm.set_sender(RELAYER);
+    c.revoke_confirmation(a).unwrap();
+    vm.set_block_timestamp(NOW + 601);
+    assert_eq!(c.revoke_confirmation(b), Err(enc(RevokeWindowClosed { messageId: b })));
+}
+
+#[test]
+fn revoke_claws_back_the_unclaimed_reward() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let id = send(&vm, &mut c);
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(c.messages_relayed_by(RELAYER, U256::ZERO, u(10)), vec![id]);
+    c.revoke_confirmation(id).unwrap();
+    assert_eq!(c.get_message_status(id), Ok(STATUS_PENDING));
+    assert_eq!(c.pending_withdrawal(RELAYER), U256::ZERO);
+    assert_eq!(c.relayers.getter(RELAYER).stake.get(), u(MIN_STAKE));
+    assert!(c.messages_relayed_by(RELAYER, U256::ZERO, u(10)).is_empty());
+    assert_eq!(c.pending_escrow(), u(FEE));
+    assert_eq!(c.protocol_fee_balance.get(), u(FEE));
+}
+
+#[test]
+fn revoke_after_claiming_takes_stake_above_the_minimum() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let id = send(&vm, &mut c);
+    confirm(&vm, &mut c, id).unwrap();
+    allow_transfer(&vm, RELAYER, u(800_000));
+    c.claim_rewards().unwrap();
+    let required = u(MIN_STAKE + 800_000);
+    assert_eq!(c.revoke_confirmation(id), Err(enc(InsufficientStake { required, provided: u(MIN_STAKE) })));
+
+    let other = address!("0000000000000000000000000000000000000f66");
+    register(&vm, &mut c, other, MIN_STAKE + FEE);
+    let id = send(&vm, &mut c);
+    vm.set_sender(other);
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+    allow_transfer(&vm, other, u(800_000));
+    c.claim_rewards().unwrap();
+    c.revoke_confirmation(id).unwrap();
+    assert_eq!(c.relayers.getter(other).stake.get(), u(MIN_STAKE + 200_000));
+    assert_eq!(c.get_message_status(id), Ok(STATUS_PENDING));
+}
+
+#[test]
+fn reconfirming_a_revoked_message_does_not_burn_twice() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    c.set_burn_bps(u(5_000)).unwrap();
+    let id = send(&vm, &mut c);
+    allow_transfer(&vm, BURN_ADDRESS, u(100_000));
+    confirm(&vm, &mut c, id).unwrap();
+    c.revoke_confirmation(id).unwrap();
+    assert_eq!(c.pending_escrow(), u(900_000));
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(c.total_burned(), u(100_000));
+    assert_eq!(c.pending_withdrawal(RELAYER), u(800_000));
+    assert_eq!(c.protocol_fee_balance.get(), u(100_000));
+    assert_eq!(c.pending_escrow(), u(100_000));
+    vm.set_block_timestamp(NOW + 601);
+    c.release_escrow(id).unwrap();
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+}
+
+#[test]
+fn revoke_reports_stats_and_frees_the_rate_limit_slot() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    c.set_rate_limit(u(1), u(PERIOD)).unwrap();
+    let (a, b) = (send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, a).unwrap();
+    c.revoke_confirmation(a).unwrap();
+    let stats = vm.get_emitted_logs().iter().filter(|(t, _)| t.first() == Some(&RelayerStatsUpdated::SIGNATURE_HASH)).count();
+    assert_eq!(stats, 2);
+    assert_eq!(c.relayers.getter(RELAYER).window_count.get(), U256::ZERO);
+    confirm(&vm, &mut c, b).unwrap();
+}
+
+#[test]
+fn withdraw_then_revoke_keeps_the_message_refundable() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let id = send(&vm, &mut c);
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(c.pending_escrow(), u(200_000));
+    vm.set_sender(OWNER);
+    assert_eq!(c.withdraw_fees(u(1)), Err(enc(InsufficientFees { available: U256::ZERO, requested: u(1) })));
+    assert_eq!(c.release_escrow(id), Err(enc(RevokeWindowOpen { messageId: id, until: u(NOW + 600) })));
+
+    vm.set_sender(RELAYER);
+    c.revoke_confirmation(id).unwrap();
+    assert_eq!(c.pending_escrow(), u(FEE));
+    assert_eq!(c.protocol_fee_balance.get(), u(FEE));
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(SENDER);
+    c.refund_message(id).unwrap();
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+    assert_eq!(c.protocol_fee_balance.get(), U256::ZERO);
+}
+
+#[test]
+fn release_escrow_frees_the_share_after_the_window() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let id = send(&vm, &mut c);
+    confirm(&vm, &mut c, id).unwrap();
+    vm.set_block_timestamp(NOW + 601);
+    vm.set_sender(SENDER);
+    c.release_escrow(id).unwrap();
+    assert!(emitted::<EscrowReleased>(&vm));
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+    assert_eq!(c.release_escrow(id), Err(enc(WrongStatus { messageId: id, status: STATUS_CONFIRMED })));
+    vm.set_sender(RELAYER);
+    assert_eq!(c.revoke_confirmation(id), Err(enc(RevokeWindowClosed { messageId: id })));
+    vm.set_sender(OWNER);
+    c.withdraw_fees(u(200_000)).unwrap();
+}
+
+#[test]
+fn discount_tiers_follow_confirmed_count() {
+    let (_vm, mut c) = setup();
+    for (delivered, bps) in [(0, 0), (9, 0), (10, 500), (99, 500), (100, 1_000), (10_000, 1_000)] {
+        c.sender_confirmed.setter(SENDER).set(u(delivered));
+        assert_eq!(c.sender_discount_bps(SENDER), u(bps), "{delivered} confirmed");
+    }
+}
+
+#[test]
+fn discounted_sender_is_quoted_and_charged_the_lower_fee() {
+    let (vm, mut c) = setup();
+    c.sender_confirmed.setter(SENDER).set(u(10));
+    vm.set_sender(SENDER);
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(950_000)));
+    assert_eq!(c.quote_batch_fee(vec![CHAIN, CHAIN], vec![U256::ZERO, U256::ZERO]), Ok(u(1_900_000)));
+    vm.set_sender(OWNER);
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(FEE)));
+    assert_eq!(send_with(&vm, &mut c, 949_999, &[]), Err(enc(InsufficientFee { required: u(950_000), provided: u(949_999) })));
+    send_with(&vm, &mut c, 950_000, &[]).unwrap();
+}
+
+#[test]
+fn discount_never_undercuts_the_min_fee() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_min_fee(u(990_000)).unwrap();
+    c.sender_confirmed.setter(SENDER).set(u(100));
+    vm.set_sender(SENDER);
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(990_000)));
+}
+
+fn send_digest(c: &MessageHub, data: &[u8], deadline: U256) -> B256 {
+    let nonce = c.meta_nonce(SENDER);
+    let struct_hash = keccak((SEND_MESSAGE_TYPEHASH, SENDER, CHAIN, TARGET, keccak(data), deadline, nonce).abi_encode_params());
+    keccak([b"\x19\x01".as_slice(), c.domain_separator().as_slice(), struct_hash.as_slice()].concat())
+}
+
+#[test]
+fn signed_send_is_accepted_once() {
+    let (vm, mut c) = setup();
+    let deadline = u(NOW + 60);
+    let s = sig(3);
+    mock_signer(&vm, send_digest(&c, b"hi", deadline), &s, SENDER);
+    vm.set_sender(RELAYER);
+    vm.set_value(u(FEE));
+    let id = c.send_message_with_sig(SENDER, CHAIN, TARGET, Bytes::from_static(b"hi"), deadline, s.clone()).unwrap();
+    assert_eq!(c.messages.getter(id).sender.get(), SENDER);
+    assert_eq!(c.meta_nonce(SENDER), u(1));
+    assert_eq!(
+        c.send_message_with_sig(SENDER, CHAIN, TARGET, Bytes::from_static(b"hi"), deadline, s),
+        Err(enc(InvalidSignature {}))
+    );
+}
+
+#[test]
+fn signed_send_rejects_expired_or_foreign_signatures() {
+    let (vm, mut c) = setup();
+    let deadline = u(NOW + 60);
+    let s = sig(4);
+    mock_signer(&vm, send_digest(&c, b"", deadline), &s, RELAYER);
+    vm.set_sender(RELAYER);
+    vm.set_value(u(FEE));
+    assert_eq!(c.send_message_with_sig(SENDER, CHAIN, TARGET, Bytes::new(), deadline, s.clone()), Err(enc(InvalidSignature {})));
+    vm.set_block_timestamp(NOW + 61);
+    assert_eq!(c.send_message_with_sig(SENDER, CHAIN, TARGET, Bytes::new(), deadline, s), Err(enc(SignatureExpired { deadline })));
+    assert_eq!(c.meta_nonce(SENDER), U256::ZERO);
+}
+
+#[test]
+fn unregistered_chain_uses_the_default_config() {
+    let (vm, mut c) = setup();
+    let (other, receiver) = (42u32, address!("0000000000000000000000000000000000000d45"));
+    vm.set_sender(SENDER);
+    assert_eq!(c.calculate_fee(other), Err(enc(ChainNotSupported { chainId: other })));
+    vm.set_sender(OWNER);
+    c.set_default_chain_config(true, receiver, u(2 * FEE)).unwrap();
+    vm.set_sender(SENDER);
+    assert_eq!(c.calculate_fee(other), Ok(u(2 * FEE)));
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(FEE)));
+    vm.set_value(u(2 * FEE));
+    let id = c.send_message(other, TARGET, Bytes::new()).unwrap();
+    vm.set_value(U256::ZERO);
+    assert_eq!(c.messages.getter(id).destination_chain.get(), U32::from(other));
+    confirm(&vm, &mut c, id).unwrap();
+}
+
+#[test]
+fn calculate_fee_rejects_a_disabled_chain() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(SENDER);
+    assert_eq!(c.calculate_fee(CHAIN), Err(enc(ChainNotSupported { chainId: CHAIN })));
+    assert_eq!(c.calculate_fee(CHAIN), c.quote_fee(CHAIN, U256::ZERO, PRIORITY_STANDARD));
+}
+
+#[test]
+fn category_is_stored_and_emitted() {
+    let (vm, mut c) = setup();
+    let category = FixedBytes::<4>::from([0xca, 0xfe, 0xf0, 0x0d]);
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let id = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, category, Bytes::new()).unwrap();
+    assert_eq!(c.get_message_category(id), Ok(category));
+    let sent = MessageSent { messageId: id, sender: SENDER, destinationChain: CHAIN, target: TARGET, data: Bytes::new(), fee: u(FEE), isTest: false, category };
+    assert!(vm.get_emitted_logs().iter().any(|(topics, data)| topics[0] == MessageSent::SIGNATURE_HASH && *data == sent.encode_data()));
+}
+
+fn merkle_pair(a: B256, b: B256) -> B256 {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    keccak([a.as_slice(), b.as_slice()].concat())
+}
+
+#[test]
+fn merkle_mode_checks_inclusion_under_the_state_root() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_proof_mode(CHAIN, PROOF_MERKLE).unwrap();
+    let ids: Vec<U256> = (0..4).map(|_| send(&vm, &mut c)).collect();
+    let leaves: Vec<B256> = ids.iter().map(|id| keccak((*id, CHAIN, TARGET).abi_encode_params())).collect();
+    let (left, right) = (merkle_pair(leaves[0], leaves[1]), merkle_pair(leaves[2], leaves[3]));
+    let proof = Bytes::from([leaves[1].as_slice(), right.as_slice()].concat());
+
+    vm.set_sender(RELAYER);
+    assert_eq!(c.confirm_delivery(ids[0], proof.clone()), Err(enc(InvalidProof {})));
+    vm.set_sender(OWNER);
+    c.submit_state_root(CHAIN, merkle_pair(left, right)).unwrap();
+    vm.set_sender(RELAYER);
+    assert_eq!(c.confirm_delivery(ids[2], proof.clone()), Err(enc(InvalidProof {})));
+    c.confirm_delivery(ids[0], proof).unwrap();
+    let proof = Bytes::from([leaves[2].as_slice(), left.as_slice()].concat());
+    c.confirm_delivery(ids[3], proof).unwrap();
+    assert_eq!(c.get_message_status(ids[3]), Ok(STATUS_CONFIRMED));
+}
+
+/// `TestVM` mocks can't run a receiver's code, so a re-entrant call is modelled
+/// by setting the lock as an outer call would hold it during its transfer. Each
+/// call is set up to pass its own checks, so the lock is what rejects it.
+#[test]
+fn eth_sending_entry_points_are_blocked_while_locked() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    vm.set_sender(OWNER);
+    c.set_treasury(RELAYER).unwrap();
+    vm.set_balance(vm.contract_address(), u(MIN_STAKE + FEE + 5));
+    c.locked.set(true);
+    vm.set_sender(RELAYER);
+    let blocked = Err(enc(Reentrancy {}));
+    assert_eq!(c.exit_relayer(), blocked);
+    assert_eq!(c.register_relayer(), blocked);
+    assert_eq!(c.withdraw_stake(U256::ZERO), blocked);
+    assert_eq!(c.claim_rewards(), blocked);
+    assert_eq!(c.withdraw_treasury(), blocked);
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), blocked);
+    assert_eq!(c.confirm_delivery_batch(vec![id], vec![Bytes::new()]), Err(enc(Reentrancy {})));
+    vm.set_sender(OWNER);
+    assert_eq!(c.sweep_unaccounted(), blocked);
+    assert_eq!(c.migrate_fees(OWNER, U256::ZERO), blocked);
+    c.locked.set(false);
+
+    confirm(&vm, &mut c, id).unwrap();
+    assert!(!c.locked.get());
+    allow_transfer(&vm, RELAYER, u(800_000));
+    c.claim_rewards().unwrap();
+    assert!(!c.locked.get());
+    allow_transfer(&vm, RELAYER, u(MIN_STAKE));
+    c.exit_relayer().unwrap();
+    assert!(!c.locked.get());
+    assert!(!c.relayers.getter(RELAYER).active.get());
+}
+
+#[test]
+fn sweep_moves_only_the_surplus() {
+    let (vm, mut c) = setup();
+    send(&vm, &mut c);
+    let accounted = MIN_STAKE + FEE;
+    vm.set_balance(vm.contract_address(), u(accounted));
+    vm.set_sender(OWNER);
+    assert_eq!(c.sweep_unaccounted(), Err(enc(NothingToSweep {})));
+    vm.set_balance(vm.contract_address(), u(accounted + 5));
+    assert_eq!(c.balance_breakdown(), Ok((u(MIN_STAKE), u(FEE), U256::ZERO, U256::ZERO, u(5))));
+    allow_transfer(&vm, OWNER, u(5));
+    c.sweep_unaccounted().unwrap();
+    assert!(emitted::<UnaccountedSwept>(&vm));
+    vm.set_sender(SENDER);
+    assert_eq!(c.sweep_unaccounted(), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn claim_deadline_has_a_floor_and_fresh_credit_is_safe() {
+    let (vm, mut c) = setup();
+    let (short, min) = (u(86_400), u(MIN_CLAIM_DEADLINE));
+    vm.set_sender(OWNER);
+    assert_eq!(c.set_claim_deadline(short), Err(enc(InvalidClaimDeadline { period: short, min })));
+    c.set_claim_deadline(min).unwrap();
+    let id = send(&vm, &mut c);
+    confirm(&vm, &mut c, id).unwrap();
+    vm.set_sender(OWNER);
+    vm.set_block_timestamp(NOW + MIN_CLAIM_DEADLINE);
+    assert_eq!(c.escheat(RELAYER), Err(enc(NotEscheatable { account: RELAYER })));
+    vm.set_block_timestamp(NOW + MIN_CLAIM_DEADLINE + 1);
+    c.escheat(RELAYER).unwrap();
+    assert_eq!(c.pending_withdrawal(RELAYER), U256::ZERO);
+    assert_eq!(c.protocol_fee_balance.get(), u(FEE));
+}
+
+#[test]
+fn min_data_len_applies_to_sends_and_batch_quotes() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_chain_min_data_len(CHAIN, 4).unwrap();
+    assert_eq!(send_with(&vm, &mut c, FEE, b"abc"), Err(enc(DataTooShort { min: 4, actual: u(3) })));
+    send_with(&vm, &mut c, FEE, b"abcd").unwrap();
+    assert_eq!(c.quote_batch_fee(vec![CHAIN, CHAIN], vec![u(4), u(3)]), Err(enc(DataTooShort { min: 4, actual: u(3) })));
+    assert_eq!(c.quote_batch_fee(vec![CHAIN, CHAIN], vec![u(4), u(64)]), Ok(u(2 * FEE)));
+}
+
+#[test]
+fn operator_confirms_only_after_accepting() {
+    let (vm, mut c) = setup();
+    let op = address!("0000000000000000000000000000000000000f77");
+    vm.set_sender(RELAYER);
+    c.set_operator(op).unwrap();
+    assert_eq!(c.pending_operator(RELAYER), op);
+    let id = send(&vm, &mut c);
+    vm.set_sender(op);
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(RelayerNotActive { relayer: op })));
+    vm.set_sender(SENDER);
+    assert_eq!(c.accept_operator(RELAYER), Err(enc(Unauthorized { caller: SENDER })));
+    vm.set_sender(op);
+    c.accept_operator(RELAYER).unwrap();
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+    assert_eq!(c.messages.getter(id).relayer.get(), RELAYER);
+    assert_eq!(c.pending_withdrawal(RELAYER), u(800_000));
+    assert_eq!(c.pending_withdrawal(op), U256::ZERO);
+    vm.set_value(u(MIN_STAKE));
+    assert_eq!(c.register_relayer(), Err(enc(Unauthorized { caller: op })));
+}
+
+#[test]
+fn registered_relayer_cannot_be_proposed_as_operator() {
+    let (vm, mut c) = setup();
+    let other = address!("0000000000000000000000000000000000000f66");
+    register(&vm, &mut c, other, MIN_STAKE);
+    vm.set_sender(RELAYER);
+    assert_eq!(c.set_operator(other), Err(enc(Unauthorized { caller: RELAYER })));
+}
+
+#[test]
+fn ordered_chain_unblocks_after_refund() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_chain_ordered(CHAIN, true).unwrap();
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let first = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, u(NOW + 60), FixedBytes::ZERO, Bytes::new()).unwrap();
+    vm.set_value(U256::ZERO);
+    let second = send(&vm, &mut c);
+    assert_eq!(confirm(&vm, &mut c, second), Err(enc(OutOfOrder { messageId: second, previousId: first })));
+    vm.set_block_timestamp(NOW + 61);
+    vm.set_sender(SENDER);
+    c.refund_message(first).unwrap();
+    confirm(&vm, &mut c, second).unwrap();
+}
+
+#[test]
+fn chain_manager_sets_chain_config_but_not_protocol_params() {
+    let (vm, mut c) = setup();
+    let manager = address!("0000000000000000000000000000000000000f88");
+    vm.set_sender(OWNER);
+    c.grant_role(manager, CHAIN_MANAGER_ROLE).unwrap();
+    vm.set_sender(manager);
+    c.set_chain_base_fee(CHAIN, u(2 * FEE)).unwrap();
+    c.set_chain_ordered(CHAIN, true).unwrap();
+    c.set_chain_min_data_len(CHAIN, 1).unwrap();
+    c.set_proof_mode(CHAIN, PROOF_MERKLE).unwrap();
+    c.submit_state_root(CHAIN, B256::repeat_byte(1)).unwrap();
+    assert_eq!(c.set_burn_bps(u(100)), Err(enc(Unauthorized { caller: manager })));
+    assert_eq!(c.withdraw_fees(U256::ZERO), Err(enc(Unauthorized { caller: manager })));
+    vm.set_sender(SENDER);
+    assert_eq!(c.set_chain_ordered(CHAIN, false), Err(enc(Unauthorized { caller: SENDER })));
+    assert_eq!(c.set_proof_mode(CHAIN, PROOF_SIGNATURE), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn priority_multiplier_is_capped() {
+    let (vm, mut c) = setup();
+    let max = u(MAX_FEE_MULTIPLIER_BPS);
+    vm.set_sender(OWNER);
+    assert_eq!(c.set_priority_multiplier(PRIORITY_FAST, max + u(1)), Err(enc(MultiplierTooHigh { bps: max + u(1), max })));
+    c.set_priority_multiplier(PRIORITY_FAST, u(15_000)).unwrap();
+    assert_eq!(c.quote_fee(CHAIN, U256::ZERO, PRIORITY_FAST), Ok(u(1_500_000)));
+    assert_eq!(c.quote_fee(CHAIN, U256::ZERO, PRIORITY_STANDARD), Ok(u(FEE)));
+    assert_eq!(c.quote_fee(CHAIN, U256::ZERO, PRIORITY_FAST + 1), Err(enc(InvalidPriority { priority: PRIORITY_FAST + 1 })));
+}
+
+#[test]
+fn zero_init_params_are_rejected() {
+    let vm = TestVM::default();
+    let mut c = MessageHub::from(&vm);
+    vm.set_sender(OWNER);
+    assert_eq!(c.initialize(U256::ZERO, u(PERIOD)), Err(enc(InvalidInitParams { minStake: U256::ZERO, challengePeriod: u(PERIOD) })));
+    assert_eq!(c.initialize(u(MIN_STAKE), U256::ZERO), Err(enc(InvalidInitParams { minStake: u(MIN_STAKE), challengePeriod: U256::ZERO })));
+    c.initialize(u(MIN_STAKE), u(PERIOD)).unwrap();
+    assert_eq!(c.set_min_stake(U256::ZERO), Err(enc(InvalidMinStake {})));
+    c.set_min_stake(u(1)).unwrap();
+}
+
+#[test]
+fn claim_reserves_the_message_until_it_expires() {
+    let (vm, mut c) = setup();
+    let other = address!("0000000000000000000000000000000000000f66");
+    register(&vm, &mut c, other, MIN_STAKE);
+    vm.set_sender(OWNER);
+    c.set_exclusivity_window(u(60)).unwrap();
+    let id = send(&vm, &mut c);
+    vm.set_sender(RELAYER);
+    c.claim_message(id).unwrap();
+    assert!(emitted::<MessageClaimed>(&vm));
+    assert_eq!(c.claim_message(id), Err(enc(AlreadyClaimed { messageId: id })));
+
+    vm.set_sender(other);
+    assert_eq!(c.claim_message(id), Err(enc(MessageClaimedByOther { messageId: id, claimedBy: RELAYER })));
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(MessageClaimedByOther { messageId: id, claimedBy: RELAYER })));
+
+    vm.set_block_timestamp(NOW + 60);
+    c.claim_message(id).unwrap();
+    vm.set_sender(RELAYER);
+    assert_eq!(c.claim_message(id), Err(enc(MessageClaimedByOther { messageId: id, claimedBy: other })));
+    vm.set_sender(other);
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+}
+
+#[test]
+fn claim_applies_the_delivery_checks() {
+    let (vm, mut c) = setup();
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let deadline = u(NOW + 10);
+    let late = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, deadline, FixedBytes::ZERO, Bytes::new()).unwrap();
+    vm.set_value(U256::ZERO);
+    let own = send(&vm, &mut c);
+    register(&vm, &mut c, SENDER, MIN_STAKE);
+    assert_eq!(c.claim_message(own), Err(enc(SelfRelayForbidden { messageId: own })));
+
+    vm.set_block_timestamp(NOW + 11);
+    vm.set_sender(RELAYER);
+    assert_eq!(c.claim_message(late), Err(enc(DeliveryDeadlinePassed { messageId: late, deadline })));
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(RELAYER);
+    assert_eq!(c.claim_message(own), Err(enc(ChainNotSupported { chainId: CHAIN })));
+}
+
+#[test]
+fn operator_cannot_confirm_its_own_message() {
+    let (vm, mut c) = setup();
+    vm.set_sender(RELAYER);
+    c.set_operator(SENDER).unwrap();
+    vm.set_sender(SENDER);
+    c.accept_operator(RELAYER).unwrap();
+    let id = send(&vm, &mut c);
+    assert_eq!(c.claim_message(id), Err(enc(SelfRelayForbidden { messageId: id })));
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(SelfRelayForbidden { messageId: id })));
+    confirm(&vm, &mut c, id).unwrap();
+}
+
+#[test]
+fn ownership_handover_moves_every_role() {
+    let (vm, mut c) = setup();
+    let (admin, next) = (address!("0000000000000000000000000000000000000f88"), address!("0000000000000000000000000000000000000f99"));
+    vm.set_sender(OWNER);
+    c.grant_role(admin, ADMIN_ROLE).unwrap();
+    vm.set_sender(admin);
+    assert_eq!(c.transfer_ownership(next), Err(enc(Unauthorized { caller: admin })));
+    vm.set_sender(OWNER);
+    c.transfer_ownership(next).unwrap();
+    vm.set_sender(next);
+    c.accept_ownership().unwrap();
+    assert_eq!(c.previous_owner(), OWNER);
+    for role in [ADMIN_ROLE, CHAIN_MANAGER_ROLE, FEE_MANAGER_ROLE] {
+        assert!(!c.has_role(OWNER, role));
+        assert!(c.has_role(next, role));
+    }
+    vm.set_sender(OWNER);
+    assert_eq!(c.set_chain_base_fee(CHAIN, u(2 * FEE)), Err(enc(Unauthorized { caller: OWNER })));
+    assert_eq!(c.withdraw_fees(U256::ZERO), Err(enc(Unauthorized { caller: OWNER })));
+}
+
+#[test]
+fn combined_registration_waits_for_the_operator_to_accept() {
+    let (vm, mut c) = setup();
+    let (relayer, op) = (address!("0000000000000000000000000000000000000f66"), address!("0000000000000000000000000000000000000f77"));
+    vm.set_sender(relayer);
+    vm.set_value(u(MIN_STAKE));
+    c.register_relayer_with_operator(op).unwrap();
+    vm.set_value(U256::ZERO);
+    assert!(emitted::<RelayerRegistered>(&vm));
+    assert!(emitted::<OperatorProposed>(&vm));
+    assert!(!emitted::<OperatorSet>(&vm));
+    let id = send(&vm, &mut c);
+    vm.set_sender(op);
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(RelayerNotActive { relayer: op })));
+    c.accept_operator(relayer).unwrap();
+    assert!(emitted::<OperatorSet>(&vm));
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+    assert_eq!(c.messages.getter(id).relayer.get(), relayer);
+}
+
+#[test]
+fn send_rejects_a_deadline_already_passed() {
+    let (vm, mut c) = setup();
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    for deadline in [u(NOW - 1), u(NOW)] {
+        let r = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, deadline, FixedBytes::ZERO, Bytes::new());
+        assert_eq!(r, Err(enc(InvalidDeadline { deadline })));
+    }
+    c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, u(NOW + 1), FixedBytes::ZERO, Bytes::new()).unwrap();
+}
+
+#[test]
+fn batch_skips_duplicates_bad_proofs_and_out_of_order_ids() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_chain_ordered(CHAIN, true).unwrap();
+    c.set_proof_mode(CHAIN, PROOF_SIGNATURE).unwrap();
+    c.set_require_third_party_proof(true).unwrap();
+    let (a, b, d) = (send(&vm, &mut c), send(&vm, &mut c), send(&vm, &mut c));
+    let proofs: Vec<Bytes> = (5..8).map(sig).collect();
+    mock_signer(&vm, delivery_digest(a, CHAIN, TARGET), &proofs[0], RECEIVER);
+    mock_signer(&vm, delivery_digest(b, CHAIN, TARGET), &proofs[1], RECEIVER);
+    mock_signer(&vm, delivery_digest(d, CHAIN, TARGET), &proofs[2], SENDER);
+
+    vm.set_sender(RELAYER);
+    let ids = vec![b, a, a, d];
+    let batch = vec![proofs[1].clone(), proofs[0].clone(), proofs[0].clone(), proofs[2].clone()];
+    assert_eq!(c.confirm_delivery_batch(ids, batch), Ok(vec![b, a, d]));
+    assert_eq!(c.get_message_status(a), Ok(STATUS_CONFIRMED));
+    assert_eq!(c.get_message_status(b), Ok(STATUS_PENDING));
+    assert_eq!(c.get_message_status(d), Ok(STATUS_PENDING));
+    assert_eq!(c.pending_withdrawal(RELAYER), u(800_000));
+    assert_eq!(c.confirm_delivery_batch(vec![b], vec![proofs[1].clone()]), Ok(vec![]));
+}
+
+#[test]
+fn batch_skips_ids_past_the_rate_limit() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_rate_limit(u(2), u(PERIOD)).unwrap();
+    let ids: Vec<U256> = (0..3).map(|_| send(&vm, &mut c)).collect();
+    vm.set_sender(RELAYER);
+    assert_eq!(c.confirm_delivery_batch(ids.clone(), vec![Bytes::new(); 3]), Ok(vec![ids[2]]));
+    assert_eq!(c.get_message_status(ids[2]), Ok(STATUS_PENDING));
+    assert_eq!(c.relayers.getter(RELAYER).window_count.get(), u(2));
+    vm.set_block_timestamp(NOW + PERIOD);
+    assert_eq!(c.confirm_delivery_batch(vec![ids[2]], vec![Bytes::new()]), Ok(vec![]));
+}
+
+#[test]
+fn rate_limit_rejects_confirmations_until_the_window_resets() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_rate_limit(u(2), u(PERIOD)).unwrap();
+    let ids: Vec<U256> = (0..4).map(|_| send(&vm, &mut c)).collect();
+    confirm(&vm, &mut c, ids[0]).unwrap();
+    vm.set_block_timestamp(NOW + 10);
+    confirm(&vm, &mut c, ids[1]).unwrap();
+    let limited = Err(enc(RateLimited { relayer: RELAYER, windowEnd: u(NOW + PERIOD) }));
+    assert_eq!(confirm(&vm, &mut c, ids[2]), limited);
+    vm.set_block_timestamp(NOW + PERIOD - 1);
+    assert_eq!(confirm(&vm, &mut c, ids[2]), limited);
+
+    vm.set_block_timestamp(NOW + PERIOD);
+    confirm(&vm, &mut c, ids[2]).unwrap();
+    assert_eq!(c.relayers.getter(RELAYER).window_start.get(), u(NOW + PERIOD));
+    assert_eq!(c.relayers.getter(RELAYER).window_count.get(), u(1));
+    confirm(&vm, &mut c, ids[3]).unwrap();
+}
+
+#[test]
+fn rate_limit_is_off_when_unset() {
+    let (vm, mut c) = setup();
+    let ids: Vec<U256> = (0..5).map(|_| send(&vm, &mut c)).collect();
+    for id in ids { confirm(&vm, &mut c, id).unwrap(); }
+}
+
+#[test]
+fn bumped_fee_is_escrowed_then_paid_out_on_confirmation() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_burn_bps(u(5_000)).unwrap();
+    let id = send(&vm, &mut c);
+    vm.set_sender(SENDER);
+    vm.set_value(u(500_000));
+    c.bump_fee(id).unwrap();
+    vm.set_value(U256::ZERO);
+    assert!(emitted::<FeeBumped>(&vm));
+    assert_eq!(c.messages.getter(id).reward.get(), u(1_200_000));
+    assert_eq!(c.messages.getter(id).burn.get(), u(150_000));
+    assert_eq!(c.pending_escrow(), u(1_500_000));
+    assert_eq!(c.protocol_fee_balance.get(), u(1_500_000));
+
+    allow_transfer(&vm, BURN_ADDRESS, u(150_000));
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(c.pending_withdrawal(RELAYER), u(1_200_000));
+    assert_eq!(c.total_burned(), u(150_000));
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+    assert_eq!(c.protocol_fee_balance.get(), u(150_000));
+    vm.set_sender(SENDER);
+    vm.set_value(u(1));
+    assert_eq!(c.bump_fee(id), Err(enc(WrongStatus { messageId: id, status: STATUS_CONFIRMED })));
+}
+
+#[test]
+fn protocol_stats_count_confirmed_and_failed_messages() {
+    let (vm, mut c) = setup();
+    let (done, reported, refunded) = (send(&vm, &mut c), send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, done).unwrap();
+    let proof = sig(9);
+    mock_signer(&vm, failure_digest(reported, CHAIN, TARGET), &proof, RECEIVER);
+    c.report_delivery_failure(reported, proof).unwrap();
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    vm.set_sender(SENDER);
+    c.refund_message(refunded).unwrap();
+
+    let (messages, chains, .., confirmed, failed) = c.protocol_stats();
+    assert_eq!((messages, chains), (u(3), u(1)));
+    assert_eq!((confirmed, failed), (u(1), u(2)));
+    assert_eq!(c.total_failed(), u(2));
+}
+
+#[test]
+fn test_flagged_messages_are_listed_separately() {
+    let (vm, mut c) = setup();
+    let live = send(&vm, &mut c);
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let flagged: Vec<U256> = (0..3)
+        .map(|_| c.send_message_extended(CHAIN, TARGET, Bytes::new(), true, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, FixedBytes::ZERO, Bytes::new()).unwrap())
+        .collect();
+    vm.set_value(U256::ZERO);
+    assert_eq!(c.is_test_message(live), Ok(false));
+    assert!(flagged.iter().all(|id| c.is_test_message(*id) == Ok(true)));
+    assert_eq!(c.is_test_message(u(99)), Err(enc(MessageNotFound { messageId: u(99) })));
+    assert_eq!(c.test_messages.len(), 3);
+    assert_eq!(c.get_test_messages(U256::ZERO, u(10)), flagged);
+    assert_eq!(c.get_test_messages(u(1), u(1)), vec![flagged[1]]);
+    assert!(c.get_test_messages(u(3), u(10)).is_empty());
+    confirm(&vm, &mut c, flagged[0]).unwrap();
+    assert_eq!(c.get_test_messages(U256::ZERO, u(10)), flagged);
+}
+
+#[test]
+fn strict_target_rejects_the_chain_receiver() {
+    let (vm, mut c) = setup();
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let loose = c.send_message(CHAIN, RECEIVER, Bytes::new()).unwrap();
+    vm.set_sender(OWNER);
+    c.set_chain_strict_target(CHAIN, true).unwrap();
+    vm.set_sender(SENDER);
+    assert_eq!(c.send_message(CHAIN, RECEIVER, Bytes::new()), Err(enc(InvalidTarget { target: RECEIVER })));
+    vm.set_value(U256::ZERO);
+    let id = send(&vm, &mut c);
+    assert_eq!(c.update_target(id, RECEIVER), Err(enc(InvalidTarget { target: RECEIVER })));
+    assert_eq!(c.message_count(), u(2));
+    assert_eq!(c.get_message_target(loose), Ok(RECEIVER));
+    vm.set_sender(SENDER);
+    assert_eq!(c.set_chain_strict_target(CHAIN, false), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn top_relayers_ranks_by_successful_confirmations() {
+    let (vm, mut c) = setup();
+    let (busy, idle) = (address!("0000000000000000000000000000000000000f66"), address!("0000000000000000000000000000000000000f77"));
+    register(&vm, &mut c, busy, MIN_STAKE);
+    register(&vm, &mut c, idle, MIN_STAKE);
+    let ids: Vec<U256> = (0..3).map(|_| send(&vm, &mut c)).collect();
+    confirm(&vm, &mut c, ids[0]).unwrap();
+    vm.set_sender(busy);
+    c.confirm_delivery(ids[1], Bytes::new()).unwrap();
+    c.confirm_delivery(ids[2], Bytes::new()).unwrap();
+    assert_eq!(c.top_relayers(u(10)), vec![(busy, u(2)), (RELAYER, u(1)), (idle, U256::ZERO)]);
+    assert_eq!(c.top_relayers(u(1)), vec![(busy, u(2))]);
+    assert!(c.top_relayers(U256::ZERO).is_empty());
+    assert_eq!(c.top_relayers(U256::MAX).len(), 3);
+}
+
+#[test]
+fn zero_target_is_rejected_before_any_write() {
+    let (vm, mut c) = setup();
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    assert_eq!(c.send_message(CHAIN, Address::ZERO, Bytes::new()), Err(enc(ZeroAddress {})));
+    vm.set_value(U256::ZERO);
+    assert_eq!(c.message_count(), U256::ZERO);
+    assert_eq!(c.pending_escrow(), U256::ZERO);
+    assert_eq!(c.messages_to_chain(CHAIN), U256::ZERO);
+    let id = send(&vm, &mut c);
+    assert_eq!(c.update_target(id, Address::ZERO), Err(enc(ZeroAddress {})));
+}
+
+#[test]
+fn third_party_proof_rejects_a_relayer_signed_proof() {
+    let (vm, mut c) = setup();
+    let (a, b) = (send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, a).unwrap();
+    vm.set_sender(OWNER);
+    c.set_require_third_party_proof(true).unwrap();
+    let (own, receiver) = (sig(10), sig(11));
+    mock_signer(&vm, delivery_digest(b, CHAIN, TARGET), &own, RELAYER);
+    vm.set_sender(RELAYER);
+    assert_eq!(c.confirm_delivery(b, own), Err(enc(InvalidProof {})));
+    assert_eq!(c.confirm_delivery(b, Bytes::new()), Err(enc(InvalidProof {})));
+    mock_signer(&vm, delivery_digest(b, CHAIN, TARGET), &receiver, RECEIVER);
+    c.confirm_delivery(b, receiver).unwrap();
+}
+
+#[test]
+fn withdraw_stake_keeps_the_minimum() {
+    let (vm, mut c) = setup();
+    register(&vm, &mut c, RELAYER, 500);
+    assert_eq!(c.withdraw_stake(u(501)), Err(enc(InsufficientStake { required: u(MIN_STAKE), provided: u(MIN_STAKE - 1) })));
+    assert_eq!(c.withdraw_stake(u(2 * MIN_STAKE)), Err(enc(InsufficientStake { required: u(MIN_STAKE), provided: U256::ZERO })));
+    allow_transfer(&vm, RELAYER, u(500));
+    c.withdraw_stake(u(500)).unwrap();
+    assert!(emitted::<StakeDecreased>(&vm));
+    assert_eq!(c.relayers.getter(RELAYER).stake.get(), u(MIN_STAKE));
+    assert_eq!(c.total_active_stake(), u(MIN_STAKE));
+    assert!(c.is_active_relayer(RELAYER));
+    vm.set_sender(SENDER);
+    assert_eq!(c.withdraw_stake(u(1)), Err(enc(RelayerNotActive { relayer: SENDER })));
+}
+
+#[test]
+fn total_active_stake_tracks_every_stake_change() {
+    let (vm, mut c) = setup();
+    let other = address!("0000000000000000000000000000000000000f66");
+    assert_eq!(c.total_active_stake(), u(MIN_STAKE));
+    register(&vm, &mut c, other, MIN_STAKE + FEE);
+    register(&vm, &mut c, RELAYER, 1_000);
+    assert_eq!(c.total_active_stake(), u(2 * MIN_STAKE + FEE + 1_000));
+
+    vm.set_sender(OWNER);
+    c.set_revoke_window(u(600)).unwrap();
+    let id = send(&vm, &mut c);
+    vm.set_sender(other);
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+    allow_transfer(&vm, other, u(800_000));
+    c.claim_rewards().unwrap();
+    c.revoke_confirmation(id).unwrap();
+    assert_eq!(c.total_active_stake(), u(2 * MIN_STAKE + 200_000 + 1_000));
+
+    allow_transfer(&vm, other, u(MIN_STAKE + 200_000));
+    c.exit_relayer().unwrap();
+    assert_eq!(c.total_active_stake(), u(MIN_STAKE + 1_000));
+}
+
+#[test]
+fn rejected_proof_leaves_no_state_behind() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_proof_mode(CHAIN, PROOF_MERKLE).unwrap();
+    c.set_rate_limit(u(1), u(PERIOD)).unwrap();
+    let id = send(&vm, &mut c);
+    let leaf = keccak((id, CHAIN, TARGET).abi_encode_params());
+    vm.set_sender(RELAYER);
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(InvalidProof {})));
+    assert_eq!(c.get_message_status(id), Ok(STATUS_PENDING));
+    assert_eq!(c.relayers.getter(RELAYER).successful.get(), U256::ZERO);
+    assert_eq!(c.relayers.getter(RELAYER).window_count.get(), U256::ZERO);
+    assert_eq!(c.pending_withdrawal(RELAYER), U256::ZERO);
+    assert_eq!(c.pending_escrow(), u(FEE));
+
+    vm.set_sender(OWNER);
+    c.submit_state_root(CHAIN, leaf).unwrap();
+    vm.set_sender(RELAYER);
+    c.confirm_delivery(id, Bytes::new()).unwrap();
+    assert_eq!(c.get_message_status(id), Ok(STATUS_CONFIRMED));
+}
+
+#[test]
+fn sender_cannot_relay_its_own_message() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    register(&vm, &mut c, SENDER, MIN_STAKE);
+    assert_eq!(c.confirm_delivery(id, Bytes::new()), Err(enc(SelfRelayForbidden { messageId: id })));
+    assert_eq!(c.confirm_delivery_batch(vec![id], vec![Bytes::new()]), Ok(vec![id]));
+    confirm(&vm, &mut c, id).unwrap();
+}
+
+#[test]
+fn rotated_receiver_keys_overlap_for_the_grace_period() {
+    let (vm, mut c) = setup();
+    let new_key = address!("0000000000000000000000000000000000000d55");
+    vm.set_sender(OWNER);
+    c.set_require_third_party_proof(true).unwrap();
+    let ids: Vec<U256> = (0..3).map(|_| send(&vm, &mut c)).collect();
+    let proofs: Vec<Bytes> = (20..23).map(sig).collect();
+    mock_signer(&vm, delivery_digest(ids[0], CHAIN, TARGET), &proofs[0], RECEIVER);
+    mock_signer(&vm, delivery_digest(ids[1], CHAIN, TARGET), &proofs[1], new_key);
+    mock_signer(&vm, delivery_digest(ids[2], CHAIN, TARGET), &proofs[2], RECEIVER);
+
+    vm.set_sender(OWNER);
+    c.rotate_chain_receiver(CHAIN, new_key).unwrap();
+    let at = u(NOW + RECEIVER_ROTATION_GRACE);
+    assert_eq!(c.finalize_chain_receiver(CHAIN), Err(enc(RotationNotReady { chainId: CHAIN, activationTime: at })));
+    vm.set_sender(RELAYER);
+    c.confirm_delivery(ids[0], proofs[0].clone()).unwrap();
+    c.confirm_delivery(ids[1], proofs[1].clone()).unwrap();
+
+    vm.set_block_timestamp(NOW + RECEIVER_ROTATION_GRACE);
+    assert_eq!(c.confirm_delivery(ids[2], proofs[2].clone()), Err(enc(InvalidProof {})));
+    vm.set_sender(OWNER);
+    c.finalize_chain_receiver(CHAIN).unwrap();
+    assert_eq!(c.supported_chains.getter(U32::from(CHAIN)).receiver_address.get(), new_key);
+}
+
+#[test]
+fn enable_chain_restores_a_disabled_chain_unchanged() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.disable_chain(CHAIN).unwrap();
+    assert_eq!(c.active_chains(), Vec::<u32>::new());
+    c.enable_chain(CHAIN).unwrap();
+    assert!(emitted::<ChainEnabled>(&vm));
+    assert_eq!(c.active_chains(), vec![CHAIN]);
+    assert_eq!(c.supported_chains.getter(U32::from(CHAIN)).receiver_address.get(), RECEIVER);
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(FEE)));
+    assert_eq!(c.chain_count(), u(1));
+    assert_eq!(c.enable_chain(77), Err(enc(ChainNotSupported { chainId: 77 })));
+    vm.set_sender(SENDER);
+    assert_eq!(c.enable_chain(CHAIN), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn fee_multiplier_scales_every_chain_fee() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    let max = u(MAX_FEE_MULTIPLIER_BPS);
+    assert_eq!(c.set_fee_multiplier(max + u(1)), Err(enc(MultiplierTooHigh { bps: max + u(1), max })));
+    c.set_fee_multiplier(u(15_000)).unwrap();
+    assert_eq!(c.fee_multiplier_bps(), u(15_000));
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(1_500_000)));
+    assert_eq!(send_with(&vm, &mut c, FEE, &[]), Err(enc(InsufficientFee { required: u(1_500_000), provided: u(FEE) })));
+    send_with(&vm, &mut c, 1_500_000, &[]).unwrap();
+    vm.set_sender(OWNER);
+    c.set_fee_multiplier(U256::ZERO).unwrap();
+    assert_eq!(c.fee_multiplier_bps(), u(BPS_DENOMINATOR));
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(FEE)));
+}
+
+#[test]
+fn message_sent_indexes_the_destination_chain() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    let logs = vm.get_emitted_logs();
+    let (topics, _) = logs.iter().find(|(t, _)| t.first() == Some(&MessageSent::SIGNATURE_HASH)).unwrap();
+    assert_eq!(topics.len(), 4);
+    assert_eq!(topics[1], B256::from(id));
+    assert_eq!(topics[2], SENDER.into_word());
+    assert_eq!(topics[3], B256::from(U256::from(CHAIN)));
+}
+
+#[test]
+fn raised_min_stake_blocks_confirmation_until_topped_up() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    vm.set_sender(OWNER);
+    assert_eq!(c.set_min_stake(U256::ZERO), Err(enc(InvalidMinStake {})));
+    c.set_min_stake(u(2 * MIN_STAKE)).unwrap();
+    assert_eq!(confirm(&vm, &mut c, id), Err(enc(InsufficientStake { required: u(2 * MIN_STAKE), provided: u(MIN_STAKE) })));
+    assert!(c.is_active_relayer(RELAYER));
+    register(&vm, &mut c, RELAYER, MIN_STAKE);
+    confirm(&vm, &mut c, id).unwrap();
+    vm.set_sender(SENDER);
+    assert_eq!(c.set_min_stake(u(1)), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn challenge_period_is_bounded() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    for period in [MIN_CHALLENGE_PERIOD - 1, MAX_CHALLENGE_PERIOD + 1] {
+        assert_eq!(c.set_challenge_period(u(period)), Err(enc(InvalidChallengePeriod { period: u(period) })));
+    }
+    c.set_challenge_period(u(MIN_CHALLENGE_PERIOD)).unwrap();
+    c.set_challenge_period(u(MAX_CHALLENGE_PERIOD)).unwrap();
+    assert!(emitted::<ChallengePeriodUpdated>(&vm));
+    assert_eq!(c.challenge_period(), u(MAX_CHALLENGE_PERIOD));
+    vm.set_sender(SENDER);
+    assert_eq!(c.set_challenge_period(u(PERIOD)), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn packed_message_fields_do_not_clobber_each_other() {
+    let (vm, mut c) = setup();
+    let category = FixedBytes::from([0xde, 0xad, 0xbe, 0xef]);
+    vm.set_sender(SENDER);
+    vm.set_value(u(2 * FEE));
+    let id = c.send_message_extended(CHAIN, TARGET, Bytes::new(), true, PRIORITY_FAST, Address::ZERO, U256::ZERO, category, Bytes::new()).unwrap();
+    vm.set_value(U256::ZERO);
+    confirm(&vm, &mut c, id).unwrap();
+    let m = c.messages.getter(id);
+    assert_eq!(m.sender.get(), SENDER);
+    assert_eq!(m.destination_chain.get(), U32::from(CHAIN));
+    assert_eq!(m.status.get().to::<u8>(), STATUS_CONFIRMED);
+    assert_eq!(m.priority.get().to::<u8>(), PRIORITY_FAST);
+    assert!(m.is_test.get());
+    assert_eq!(m.category.get(), category);
+    assert!(m.exists.get());
+}
+
+#[test]
+fn rewards_accrue_until_claimed() {
+    let (vm, mut c) = setup();
+    let (a, b) = (send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, a).unwrap();
+    confirm(&vm, &mut c, b).unwrap();
+    assert_eq!(c.pending_withdrawal(RELAYER), u(1_600_000));
+    assert_eq!(c.total_claimable.get(), u(1_600_000));
+    allow_transfer(&vm, RELAYER, u(1_600_000));
+    c.claim_rewards().unwrap();
+    assert!(emitted::<RewardsClaimed>(&vm));
+    assert_eq!(c.pending_withdrawal(RELAYER), U256::ZERO);
+    assert_eq!(c.total_claimable.get(), U256::ZERO);
+}
+
+#[test]
+fn add_chains_batch_registers_each_chain_once() {
+    let (vm, mut c) = setup();
+    let (r1, r2) = (address!("0000000000000000000000000000000000000d45"), address!("0000000000000000000000000000000000000d46"));
+    vm.set_sender(OWNER);
+    assert_eq!(c.add_chains_batch(vec![11, 12], vec![r1], vec![u(FEE), u(FEE)]), Err(enc(LengthMismatch {})));
+    assert_eq!(c.add_chains_batch(vec![13], vec![Address::ZERO], vec![u(FEE)]), Err(enc(ZeroAddress {})));
+    c.add_chains_batch(vec![CHAIN, 11, 12], vec![RECEIVER, r1, r2], vec![u(FEE), u(2 * FEE), u(3 * FEE)]).unwrap();
+    let added = vm.get_emitted_logs().iter().filter(|(t, _)| t.first() == Some(&ChainAdded::SIGNATURE_HASH)).count();
+    assert_eq!(added, 4);
+    assert_eq!(c.chain_count(), u(3));
+    assert_eq!(c.active_chains(), vec![CHAIN, 11, 12]);
+    assert_eq!(c.calculate_fee(12), Ok(u(3 * FEE)));
+    vm.set_sender(SENDER);
+    assert_eq!(c.add_chains_batch(vec![14], vec![r1], vec![u(FEE)]), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn relayer_cap_limits_new_registrations_only() {
+    let (vm, mut c) = setup();
+    let (b, d) = (address!("0000000000000000000000000000000000000f66"), address!("0000000000000000000000000000000000000f77"));
+    vm.set_sender(OWNER);
+    c.set_max_relayers(u(2)).unwrap();
+    register(&vm, &mut c, b, MIN_STAKE);
+    assert_eq!(c.active_relayer_count(), u(2));
+    vm.set_sender(d);
+    vm.set_value(u(MIN_STAKE));
+    assert_eq!(c.register_relayer(), Err(enc(RelayerCapReached { maxRelayers: u(2) })));
+    vm.set_value(U256::ZERO);
+    register(&vm, &mut c, b, 1_000);
+
+    allow_transfer(&vm, b, u(MIN_STAKE + 1_000));
+    c.exit_relayer().unwrap();
+    assert_eq!(c.active_relayer_count(), u(1));
+    register(&vm, &mut c, d, MIN_STAKE);
+    vm.set_sender(OWNER);
+    c.set_max_relayers(U256::ZERO).unwrap();
+    register(&vm, &mut c, b, MIN_STAKE);
+    assert_eq!(c.active_relayer_count(), u(3));
+}
+
+#[test]
+fn set_chain_base_fee_leaves_the_receiver_alone() {
+    let (vm, mut c) = setup();
+    vm.set_sender(OWNER);
+    c.set_chain_base_fee(CHAIN, u(3 * FEE)).unwrap();
+    assert!(emitted::<ChainFeeUpdated>(&vm));
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(3 * FEE)));
+    assert_eq!(c.supported_chains.getter(U32::from(CHAIN)).receiver_address.get(), RECEIVER);
+    assert_eq!(c.set_chain_base_fee(77, u(FEE)), Err(enc(ChainNotSupported { chainId: 77 })));
+    c.disable_chain(CHAIN).unwrap();
+    assert_eq!(c.set_chain_base_fee(CHAIN, u(FEE)), Err(enc(ChainNotSupported { chainId: CHAIN })));
+}
+
+#[test]
+fn messages_relayed_by_pages_in_confirmation_order() {
+    let (vm, mut c) = setup();
+    let ids: Vec<U256> = (0..5).map(|_| send(&vm, &mut c)).collect();
+    for id in ids.iter().rev() { confirm(&vm, &mut c, *id).unwrap(); }
+    let order: Vec<U256> = ids.iter().rev().copied().collect();
+    assert_eq!(c.messages_relayed_by(RELAYER, U256::ZERO, u(10)), order);
+    assert_eq!(c.messages_relayed_by(RELAYER, u(2), u(2)), order[2..4].to_vec());
+    assert!(c.messages_relayed_by(RELAYER, u(5), u(10)).is_empty());
+    assert!(c.messages_relayed_by(SENDER, U256::ZERO, u(10)).is_empty());
+    assert_eq!(c.messages_relayed_by(RELAYER, U256::ZERO, U256::MAX).len(), 5);
+}
+
+#[test]
+fn gwei_fees_are_stored_in_wei() {
+    let (vm, mut c) = setup();
+    assert_eq!(gwei_to_wei(3), u(3_000_000_000));
+    vm.set_sender(OWNER);
+    c.add_chain_gwei(20, RECEIVER, 2).unwrap();
+    assert_eq!(c.calculate_fee(20), Ok(u(2_000_000_000)));
+    c.set_chain_base_fee_gwei(CHAIN, 5).unwrap();
+    assert_eq!(c.calculate_fee(CHAIN), Ok(u(5_000_000_000)));
+}
+
+#[test]
+fn average_fee_follows_the_smoothing_factor() {
+    let (vm, mut c) = setup();
+    assert_eq!(c.average_fee(CHAIN), U256::ZERO);
+    send(&vm, &mut c);
+    assert_eq!(c.average_fee(CHAIN), u(FEE));
+    vm.set_sender(OWNER);
+    assert_eq!(c.set_fee_ema_alpha(u(10_001)), Err(enc(InvalidBps { bps: u(10_001) })));
+    c.set_fee_ema_alpha(u(5_000)).unwrap();
+    send_with(&vm, &mut c, 3 * FEE, &[]).unwrap();
+    assert_eq!(c.average_fee(CHAIN), u(2 * FEE));
+    send_with(&vm, &mut c, 4 * FEE, &[]).unwrap();
+    assert_eq!(c.average_fee(CHAIN), u(3 * FEE));
+    assert_eq!(c.average_fee(20), U256::ZERO);
+}
+
+#[test]
+fn confirm_distinguishes_unknown_from_relayed_ids() {
+    let (vm, mut c) = setup();
+    let id = send(&vm, &mut c);
+    for unknown in [U256::ZERO, id + u(1)] {
+        assert_eq!(confirm(&vm, &mut c, unknown), Err(enc(MessageNotFound { messageId: unknown })));
+    }
+    confirm(&vm, &mut c, id).unwrap();
+    assert_eq!(confirm(&vm, &mut c, id), Err(enc(AlreadyRelayed { messageId: id })));
+}
+
+#[test]
+fn encryption_meta_is_stored_and_capped() {
+    let (vm, mut c) = setup();
+    let meta = Bytes::from(vec![7u8; MAX_ENCRYPTION_META]);
+    vm.set_sender(SENDER);
+    vm.set_value(u(FEE));
+    let long = Bytes::from(vec![7u8; MAX_ENCRYPTION_META + 1]);
+    let r = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, FixedBytes::ZERO, long);
+    assert_eq!(r, Err(enc(EncryptionMetaTooLong { length: u(257), max: u(256) })));
+    let id = c.send_message_extended(CHAIN, TARGET, Bytes::new(), false, PRIORITY_STANDARD, Address::ZERO, U256::ZERO, FixedBytes::ZERO, meta.clone()).unwrap();
+    vm.set_value(U256::ZERO);
+    assert!(emitted::<EncryptionMetaAttached>(&vm));
+    assert_eq!(c.get_message_encryption_meta(id), Ok(meta));
+    let plain = send(&vm, &mut c);
+    assert_eq!(c.get_message_encryption_meta(plain), Ok(Bytes::new()));
+    assert_eq!(c.get_message_encryption_meta(u(99)), Err(enc(MessageNotFound { messageId: u(99) })));
+}
+
+#[test]
+fn message_sent_at_time_zero_still_exists() {
+    let (vm, mut c) = setup();
+    vm.set_block_timestamp(0);
+    let id = send(&vm, &mut c);
+    assert_eq!(c.get_message_timestamp(id), Ok(U256::ZERO));
+    assert_eq!(c.get_message_status(id), Ok(STATUS_PENDING));
+    confirm(&vm, &mut c, id).unwrap();
+    let missing = id + u(1);
+    assert_eq!(c.get_message_status(missing), Err(enc(MessageNotFound { messageId: missing })));
+    assert_eq!(c.get_message_target(missing), Err(enc(MessageNotFound { messageId: missing })));
+}
+
+#[test]
+fn chain_counters_track_sent_confirmed_and_failed() {
+    let (vm, mut c) = setup();
+    let ids: Vec<U256> = (0..3).map(|_| send(&vm, &mut c)).collect();
+    assert_eq!(c.messages_to_chain(CHAIN), u(3));
+    confirm(&vm, &mut c, ids[0]).unwrap();
+    let proof = sig(30);
+    mock_signer(&vm, failure_digest(ids[1], CHAIN, TARGET), &proof, RECEIVER);
+    c.report_delivery_failure(ids[1], proof).unwrap();
+    assert_eq!(c.chain_stats(CHAIN), (u(3), u(1), u(1)));
+    assert_eq!(c.chain_stats(20), (U256::ZERO, U256::ZERO, U256::ZERO));
+}
+
+#[test]
+fn migrate_fees_moves_only_unescrowed_fees() {
+    let (vm, mut c) = setup();
+    let successor = address!("0000000000000000000000000000000000000f99");
+    let done = send(&vm, &mut c);
+    send(&vm, &mut c);
+    confirm(&vm, &mut c, done).unwrap();
+    vm.set_sender(OWNER);
+    assert_eq!(c.migrate_fees(Address::ZERO, u(1)), Err(enc(ZeroAddress {})));
+    assert_eq!(c.migrate_fees(successor, u(200_001)), Err(enc(InsufficientFees { available: u(200_000), requested: u(200_001) })));
+    allow_transfer(&vm, successor, u(200_000));
+    c.migrate_fees(successor, u(200_000)).unwrap();
+    assert!(emitted::<FeesMigrated>(&vm));
+    assert_eq!(c.protocol_fee_balance.get(), u(FEE));
+    assert_eq!(c.pending_escrow(), u(FEE));
+    vm.set_sender(SENDER);
+    assert_eq!(c.migrate_fees(successor, U256::ZERO), Err(enc(Unauthorized { caller: SENDER })));
+}
+
+#[test]
+fn local_chain_cannot_be_a_destination() {
+    let (vm, mut c) = setup();
+    let local = vm.chain_id() as u32;
+    vm.set_sender(OWNER);
+    assert_eq!(c.add_chain(local, RECEIVER, u(FEE)), Err(enc(CannotRegisterLocalChain { chainId: local })));
+    assert_eq!(c.add_chains_batch(vec![local], vec![RECEIVER], vec![u(FEE)]), Err(enc(CannotRegisterLocalChain { chainId: local })));
+    c.add_chain(local + 1, RECEIVER, u(FEE)).unwrap();
+    assert_eq!(c.chain_count(), u(2));
+}
+
+#[test]
+fn target_can_be_updated_only_while_pending() {
+    let (vm, mut c) = setup();
+    let new_target = address!("0000000000000000000000000000000000000e56");
+    let id = send(&vm, &mut c);
+    vm.set_sender(RELAYER);
+    assert_eq!(c.update_target(id, new_target), Err(enc(Unauthorized { caller: RELAYER })));
+    vm.set_sender(SENDER);
+    c.update_target(id, new_target).unwrap();
+    assert!(emitted::<TargetUpdated>(&vm));
+    assert_eq!(c.get_message_target(id), Ok(new_target));
+    confirm(&vm, &mut c, id).unwrap();
+    vm.set_sender(SENDER);
+    assert_eq!(c.update_target(id, TARGET), Err(enc(WrongStatus { messageId: id, status: STATUS_CONFIRMED })));
+    assert_eq!(c.update_target(u(99), TARGET), Err(enc(MessageNotFound { messageId: u(99) })));
+}
+
+#[test]
+fn status_views_match_each_state() {
+    let (vm, mut c) = setup();
+    let (pending, confirmed, failed) = (send(&vm, &mut c), send(&vm, &mut c), send(&vm, &mut c));
+    confirm(&vm, &mut c, confirmed).unwrap();
+    let proof = sig(31);
+    mock_signer(&vm, failure_digest(failed, CHAIN, TARGET), &proof, RECEIVER);
+    c.report_delivery_failure(failed, proof).unwrap();
+    let views = |id| (c.is_pending(id), c.is_confirmed(id), c.is_failed(id));
+    assert_eq!(views(pending), (Ok(true), Ok(false), Ok(false)));
+    assert_eq!(views(confirmed), (Ok(false), Ok(true), Ok(false)));
+    assert_eq!(views(failed), (Ok(false), Ok(false), Ok(true)));
+    let unknown: Result<bool, Vec<u8>> = Err(enc(MessageNotFound { messageId: u(99) }));
+    assert_eq!(views(u(99)), (unknown.clone(), unknown.clone(), unknown));
+}
@@ -159,7 +159,7 @@ async function relayMessage(
   try {
     const hubTx = await hub.confirmDelivery(
       message.id,
-      proof,  // execution proof (currently unused in hub but included for future use)
+      proof,  // only checked when the hub requires receiver-signed or Merkle proofs
     ) as ethers.TransactionResponse;
     const hubReceipt = await hubTx.wait();
     console.log(`  ✓  confirmDelivery mined: ${hubReceipt?.hash}`);
@@ -185,13 +185,16 @@ async function processEvent(
   processed.add(idStr);
 
   const destinationChain = Number(event.args.destinationChain);
-  const sender           = event.args.sender as string;
-  const target           = event.args.target as string;
-  const data             = event.args.data   as string;
+  const sender           = event.args.sender   as string;
+  const target           = event.args.target   as string;
+  const data             = event.args.data     as string;
+  const isTest           = event.args.isTest   as boolean;
+  const category         = event.args.category as string;
 
-  console.log(`\n📨  Message #${idStr}  →  chain ${destinationChain}`);
+  console.log(`\n📨  Message #${idStr}  →  chain ${destinationChain}${isTest ? '  (test)' : ''}`);
   console.log(`     sender: ${sender}`);
   console.log(`     target: ${target}`);
+  console.log(`     category: ${category}`);
 
   // Check if already confirmed
   let status: number;
@@ -258,7 +261,8 @@ async function main(): Promise<void> {
   const fromBlock    = Math.max(0, currentBlock - 50_000);
 
   console.log(`🔍  Scanning blocks ${fromBlock}–${currentBlock} for pending messages ...`);
-  const sentFilter = hub.filters['MessageSent']();
+  // destinationChain is an indexed topic, so only fetch messages we can deliver.
+  const sentFilter = hub.filters['MessageSent'](null, null, Object.keys(CHAINS).map(Number));
   const pastEvents = await hub.queryFilter(sentFilter, fromBlock) as ethers.EventLog[];
   console.log(`    Found ${pastEvents.length} MessageSent event(s)\n`);
 
@@ -74,17 +74,37 @@ export class ArbiLink {
   async sendMessage(params: SendMessageParams): Promise<bigint> {
     this.requireSigner();
 
-    const chainId = resolveChainId(params.to);
-    const fee     = params.fee ?? await this.calculateFee(chainId);
+    const chainId  = resolveChainId(params.to);
+    const priority = params.priority ?? 0;
+    const fee      = params.fee ?? await this.quoteFee(chainId, ethers.dataLength(params.data), priority);
+
+    // The plain 3-argument entrypoint covers the common case; anything beyond
+    // it goes through sendMessageExtended with the hub's defaults filled in.
+    const extended =
+      params.isTest !== undefined || params.priority !== undefined || params.refundAddress !== undefined ||
+      params.deadline !== undefined || params.category !== undefined || params.encryptionMeta !== undefined;
 
     let tx: ethers.TransactionResponse;
     try {
-      tx = await this.messageHub.sendMessage(
-        chainId,
-        params.target,
-        params.data,
-        { value: fee },
-      ) as ethers.TransactionResponse;
+      tx = extended
+        ? await this.messageHub.sendMessageExtended(
+            chainId,
+            params.target,
+            params.data,
+            params.isTest ?? false,
+            priority,
+            params.refundAddress ?? ethers.ZeroAddress,
+            params.deadline ?? 0n,
+            params.category ?? '0x00000000',
+            params.encryptionMeta ?? '0x',
+            { value: fee },
+          ) as ethers.TransactionResponse
+        : await this.messageHub.sendMessage(
+            chainId,
+            params.target,
+            params.data,
+            { value: fee },
+          ) as ethers.TransactionResponse;
     } catch (err) {
       throw ArbiLinkError.from(err, `Failed to send message to chain ${chainId}`);
     }
@@ -138,6 +158,8 @@ export class ArbiLink {
       let target: string | undefined;
       let data: string | undefined;
       let feePaid: bigint | undefined;
+      let isTest: boolean | undefined;
+      let category: string | undefined;
 
       if (sentLogs.length > 0) {
         const e = sentLogs[0] as ethers.EventLog;
@@ -146,6 +168,8 @@ export class ArbiLink {
         target           = e.args.target           as string;
         data             = e.args.data             as string;
         feePaid          = e.args.fee              as bigint;
+        isTest           = e.args.isTest           as boolean;
+        category         = e.args.category         as string;
       }
 
       // Enrich from MessageConfirmed event (present only when confirmed)
@@ -167,6 +191,8 @@ export class ArbiLink {
         data,
         feePaid,
         relayer,
+        isTest,
+        category,
       };
     } catch (err) {
       throw ArbiLinkError.from(err, `Failed to fetch status for message #${messageId}`);
@@ -192,6 +218,23 @@ export class ArbiLink {
     }
   }
 
+  /**
+   * Exact fee (wei) the signer must attach for a message of `dataLength` bytes at
+   * the given priority tier, including multipliers, floors and sender discounts.
+   *
+   * @example
+   * ```typescript
+   * const fee = await arbiLink.quoteFee(11155111, 68, 1); // fast tier
+   * ```
+   */
+  async quoteFee(chainId: number, dataLength: number, priority = 0): Promise<bigint> {
+    try {
+      return await this.messageHub.quoteFee(chainId, dataLength, priority) as bigint;
+    } catch (err) {
+      throw ArbiLinkError.from(err, `Failed to quote fee for chain ${chainId}`);
+    }
+  }
+
   // ── Core: watch ────────────────────────────────────────────────────────────
 
   /**
@@ -34,6 +34,10 @@ export interface Message {
   feePaid?: bigint;
   /** Relayer address, present once a relayer confirms delivery */
   relayer?: string;
+  /** Whether the sender flagged the message as a test — populated from MessageSent */
+  isTest?: boolean;
+  /** bytes4 routing discriminator chosen by the sender — populated from MessageSent */
+  category?: string;
 }
 
 // ── SDK parameter types ───────────────────────────────────────────────────────
@@ -47,6 +51,18 @@ export interface SendMessageParams {
   data: string;
   /** Override the auto-calculated fee (wei). Fetched from hub if omitted. */
   fee?: bigint;
+  /** Flag the message as a test so indexers and relayers can tell it apart */
+  isTest?: boolean;
+  /** Priority tier: 0 = standard (default), 1 = fast */
+  priority?: number;
+  /** Where refunds go; defaults to the sender */
+  refundAddress?: string;
+  /** Unix time after which the message can no longer be confirmed; 0 / omitted = none */
+  deadline?: bigint;
+  /** bytes4 hex discriminator for off-chain routing, e.g. '0xcafef00d' */
+  category?: string;
+  /** Opaque metadata for encrypted payloads (max 256 bytes) */
+  encryptionMeta?: string;
 }
 
 export interface WatchOptions {
@@ -43,6 +43,9 @@ const HUB_ABI = [
   'function owner() view returns (address)',
   'function isActiveRelayer(address) view returns (bool)',
   'function calculateFee(uint32) view returns (uint256)',
+  'function quoteFee(uint32,uint256,uint8) view returns (uint256)',
+  'function version() view returns (string)',
+  'event MessageSent(uint256 indexed messageId, address indexed sender, uint32 indexed destinationChain, address target, bytes data, uint256 fee, bool isTest, bytes4 category)',
 ];
 
 const RECEIVER_ABI = [
@@ -147,7 +150,8 @@ async function main() {
     const count    = await hub.messageCount();
     const minStake = await hub.minStake();
     const owner    = await hub.owner();
-    ok('Hub reachable', `${count} messages, owner ${owner.slice(0,10)}…`);
+    const version  = await hub.version();
+    ok('Hub reachable', `v${version}, ${count} messages, owner ${owner.slice(0,10)}…`);
     if (minStake !== MIN_STAKE) warn(`minStake is ${ethers.formatEther(minStake)} ETH (expected 1 ETH)`);
     else ok('minStake = 1 ETH');
   } catch (e) {
@@ -164,13 +168,25 @@ async function main() {
 
   for (const destChainId of [11155111, 84532, 80002]) {
     try {
-      const fee = await hub.calculateFee(destChainId);
-      ok(`Fee for chain ${destChainId}`, `${ethers.formatEther(fee)} ETH`);
+      const fee   = await hub.calculateFee(destChainId);
+      const quote = await hub.quoteFee(destChainId, 0, 0);
+      if (quote === fee) ok(`Fee for chain ${destChainId}`, `${ethers.formatEther(fee)} ETH`);
+      else fail(`Fee for chain ${destChainId}: calculateFee and quoteFee disagree`, `${fee} vs ${quote}`);
     } catch (e) {
       fail(`Fee for chain ${destChainId} failed`, (e).message.slice(0, 60));
     }
   }
 
+  // The relayer subscribes by destination chain; make sure the live event matches the ABI it uses.
+  try {
+    const latest = await arbProvider.getBlockNumber();
+    const logs   = await hub.queryFilter(hub.filters.MessageSent(), Math.max(0, latest - 10_000));
+    if (logs.length > 0) ok('MessageSent decodes with the current ABI', `${logs.length} recent event(s)`);
+    else warn('No recent MessageSent events to decode — send a test message to check the ABI');
+  } catch (e) {
+    fail('MessageSent query failed', (e).message.slice(0, 60));
+  }
+
   // ── 4. Receiver contracts ────────────────────────────────────────────────────
   section('Receiver Contracts');
 